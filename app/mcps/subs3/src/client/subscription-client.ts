@@ -1,5 +1,10 @@
 import { Connection, PublicKey, Keypair, SystemProgram, Transaction } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync
+} from "@solana/spl-token";
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import type { Subs3 } from "../../../../../target/types/subs3";
@@ -18,6 +23,10 @@ import {
   getSubscriptionPlanPda, 
   getProviderVaultPda,
   getSubscriptionPda,
+  getProviderPda,
+  getAllowedMintPda,
+  getBlockedSubscriberPda,
+  getMembershipMintPda,
   toPublicKey,
   validatePlanId,
   validatePrice,
@@ -99,7 +108,12 @@ export class SolanaSubscriptionClient {
       this.programId
     );
 
+    const [providerAccountPda] = getProviderPda(provider, this.programId);
+
     const paymentTokenMint = toPublicKey(params.paymentTokenMint);
+    // The allowlist entry is only required while the manager's mint allowlist is enabled
+    const [allowedMintPda] = getAllowedMintPda(paymentTokenMint, this.programId);
+    const allowedMintInfo = await this.connection.getAccountInfo(allowedMintPda);
 
     try {
       const transaction = await this.program.methods
@@ -115,8 +129,10 @@ export class SolanaSubscriptionClient {
           provider: provider,
           subscriptionManager: subscriptionManagerPda,
           subscriptionPlan: subscriptionPlanPda,
+          providerAccount: providerAccountPda,
           providerVault: providerVaultPda,
           paymentTokenMint: paymentTokenMint,
+          allowedMint: allowedMintInfo ? allowedMintPda : null,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -223,7 +239,7 @@ export class SolanaSubscriptionClient {
   /**
    * Build subscribe transaction (unsigned)
    */
-  async buildSubscribeTransaction(planAddress: string, subscriber: PublicKey): Promise<{
+  async buildSubscribeTransaction(planAddress: string, subscriber: PublicKey, quantity: number = 1): Promise<{
     transaction: Transaction;
     subscriptionAddress: string;
   }> {
@@ -236,13 +252,31 @@ export class SolanaSubscriptionClient {
     const [subscriptionManagerPda] = getSubscriptionManagerPda(this.programId);
 
     try {
+      const plan = await this.program.account.subscriptionPlan.fetch(planPda);
+      const [providerAccountPda] = getProviderPda(plan.owner, this.programId);
+      const [blockedSubscriberPda] = getBlockedSubscriberPda(plan.owner, subscriber, this.programId);
+
+      // Membership token accounts are only needed when the plan issues membership tokens
+      const membershipMint = plan.membershipMint
+        ? getMembershipMintPda(planPda, this.programId)[0]
+        : null;
+      const membershipTokenAccount = membershipMint
+        ? getAssociatedTokenAddressSync(membershipMint, subscriber, false, TOKEN_2022_PROGRAM_ID)
+        : null;
+
       const transaction = await this.program.methods
-        .subscribe()
+        .subscribe(quantity)
         .accountsStrict({
           subscription: subscriptionPda,
           subscriptionPlan: planPda,
+          providerAccount: providerAccountPda,
           subscriptionManager: subscriptionManagerPda,
+          blockedSubscriber: blockedSubscriberPda,
+          membershipMint,
+          membershipTokenAccount,
           subscriber: subscriber,
+          token2022Program: membershipMint ? TOKEN_2022_PROGRAM_ID : null,
+          associatedTokenProgram: membershipMint ? ASSOCIATED_TOKEN_PROGRAM_ID : null,
           systemProgram: SystemProgram.programId,
         })
         .transaction();
@@ -281,16 +315,23 @@ export class SolanaSubscriptionClient {
         plan.planId,
         this.programId
       );
+      const [providerAccountPda] = getProviderPda(plan.owner, this.programId);
+      const [subscriptionManagerPda] = getSubscriptionManagerPda(this.programId);
 
       const transaction = await this.program.methods
         .processPayment()
         .accountsStrict({
           subscription: subscriptionPda,
           subscriptionPlan: subscription.subscriptionPlan,
+          providerAccount: providerAccountPda,
           subscriberTokenAccount: subscriberTokenPda,
           providerVault: providerVaultPda,
+          subscriptionManager: subscriptionManagerPda,
+          crankerTokenAccount: null,
+          receipt: null,
           subscriber: subscriber,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: null,
         })
         .transaction();
 
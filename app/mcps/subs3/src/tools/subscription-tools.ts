@@ -76,7 +76,8 @@ export const BuildCreateSubscriptionPlanTxSchema = z.object({
 
 export const BuildSubscribeTxSchema = z.object({
   planAddress: z.string().describe("Public key address of the subscription plan to subscribe to"),
-  subscriber: z.string().describe("Subscriber's public key")
+  subscriber: z.string().describe("Subscriber's public key"),
+  quantity: z.number().int().min(1).default(1).describe("Number of seats to subscribe for (defaults to 1)")
 });
 
 export const BuildProcessPaymentTxSchema = z.object({
@@ -437,7 +438,11 @@ export class SubscriptionTools {
   async buildSubscribeTransaction(params: z.infer<typeof BuildSubscribeTxSchema>): Promise<any> {
     try {
       const subscriber = new PublicKey(params.subscriber);
-      const { transaction, subscriptionAddress } = await this.client.buildSubscribeTransaction(params.planAddress, subscriber);
+      const { transaction, subscriptionAddress } = await this.client.buildSubscribeTransaction(
+        params.planAddress,
+        subscriber,
+        params.quantity
+      );
       const preparedTx = await this.client.prepareTransaction(transaction, subscriber);
       
       // Serialize transaction for signing
@@ -456,6 +461,7 @@ export class SubscriptionTools {
           metadata: {
             planAddress: params.planAddress,
             subscriber: params.subscriber,
+            quantity: params.quantity,
             subscriptionAddress
           }
         }
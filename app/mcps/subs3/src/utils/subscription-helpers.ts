@@ -10,6 +10,50 @@ export function getSubscriptionManagerPda(programId: PublicKey): [PublicKey, num
   );
 }
 
+/**
+ * Generate provider PDA
+ */
+export function getProviderPda(provider: PublicKey, programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("provider"), provider.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Generate allowed mint PDA
+ */
+export function getAllowedMintPda(mint: PublicKey, programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("allowed_mint"), mint.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Generate blocked subscriber PDA
+ */
+export function getBlockedSubscriberPda(
+  provider: PublicKey,
+  subscriber: PublicKey,
+  programId: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("blocked_subscriber"), provider.toBuffer(), subscriber.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Generate membership mint PDA
+ */
+export function getMembershipMintPda(subscriptionPlan: PublicKey, programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("membership_mint"), subscriptionPlan.toBuffer()],
+    programId
+  );
+}
+
 /**
  * Generate subscription plan PDA
  */
@@ -0,0 +1,161 @@
+//! Entitlement checks for programs gating actions on a subs3 subscription.
+//!
+//! These read `Subscription` and `SubscriptionMember` account data directly, which is
+//! cheaper than a CPI into `verify_entitlement` and applies the same rules.
+
+use anchor_lang::prelude::*;
+use subs3::{Subscription, SubscriptionError, SubscriptionMember};
+
+/// Deserialize a subs3 account, checking the owner program and discriminator
+pub fn load_account<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<T> {
+    if info.owner != &T::owner() {
+        return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Whether `wallet` has access to `subscription_plan` at `now`.
+///
+/// `subscription` must be the subscription account for the plan. `subscription_member`
+/// is only read when `wallet` is not the subscriber, to check a group plan member link.
+pub fn is_entitled(
+    wallet: &Pubkey,
+    subscription_plan: &Pubkey,
+    subscription: &AccountInfo,
+    subscription_member: Option<&AccountInfo>,
+    now: i64,
+) -> Result<bool> {
+    let loaded = load_account::<Subscription>(subscription)?;
+    if loaded.subscription_plan != *subscription_plan {
+        return Ok(false);
+    }
+    if loaded.subscriber == *wallet {
+        return Ok(loaded.is_entitled(now));
+    }
+
+    match subscription_member {
+        Some(info) => {
+            let member = load_account::<SubscriptionMember>(info)?;
+            Ok(member.member == *wallet && member.is_entitled(subscription.key(), &loaded, now))
+        }
+        None => Ok(false),
+    }
+}
+
+/// Same as `is_entitled`, failing with `SubscriptionError::NotEntitled` instead of
+/// returning `false`
+pub fn require_entitled(
+    wallet: &Pubkey,
+    subscription_plan: &Pubkey,
+    subscription: &AccountInfo,
+    subscription_member: Option<&AccountInfo>,
+    now: i64,
+) -> Result<()> {
+    require!(
+        is_entitled(wallet, subscription_plan, subscription, subscription_member, now)?,
+        SubscriptionError::NotEntitled
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn subscription(subscriber: Pubkey, subscription_plan: Pubkey) -> Subscription {
+        let mut subscription = Subscription {
+            subscriber,
+            subscription_plan,
+            start_time: 0,
+            next_payment_due: 0,
+            is_active: false,
+            is_paused: false,
+            paused_at: None,
+            cancelled_at: None,
+            total_payments_made: 0,
+            total_amount_paid: 0,
+            payment_nonce: 0,
+            bump: 0,
+            prepaid_through: None,
+            current_period_usage: 0,
+            quantity: 0,
+            credit_balance: 0,
+            add_ons: Vec::new(),
+            payer: subscriber,
+            renews_after_gift: false,
+            organization: None,
+            member_count: 0,
+            payment_history: Vec::new(),
+            payment_history_cursor: 0,
+            spending_limits: Default::default(),
+            spending_day: 0,
+            spending_buckets: [0; subs3::SPENDING_BUCKETS],
+            paid_amount: 0,
+            paid_from: 0,
+            paid_until: 0,
+            prepaid_amount: 0,
+            paid_by_subscriber: 0,
+            paid_sponsor: None,
+        };
+        subscription.initialize(subscriber, subscription_plan, NOW - 100, NOW + 100, 1, 255);
+        subscription
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn subscriber_is_entitled_until_payment_lapses() {
+        let (key, owner, wallet, plan) = (Pubkey::new_unique(), subs3::ID, Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = serialize(&subscription(wallet, plan));
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        assert!(is_entitled(&wallet, &plan, &info, None, NOW).unwrap());
+        assert!(!is_entitled(&wallet, &Pubkey::new_unique(), &info, None, NOW).unwrap());
+        assert!(!is_entitled(&Pubkey::new_unique(), &plan, &info, None, NOW).unwrap());
+        assert!(!is_entitled(&wallet, &plan, &info, None, NOW + 100 + subs3::PAYMENT_GRACE_PERIOD).unwrap());
+    }
+
+    #[test]
+    fn member_needs_an_accepted_link() {
+        let (key, owner, plan) = (Pubkey::new_unique(), subs3::ID, Pubkey::new_unique());
+        let (member_key, member_wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = serialize(&subscription(Pubkey::new_unique(), plan));
+        let mut link = SubscriptionMember {
+            subscription: key,
+            member: member_wallet,
+            invited_at: NOW - 50,
+            accepted_at: None,
+            bump: 255,
+        };
+        let mut pending_data = serialize(&link);
+        link.accepted_at = Some(NOW - 10);
+        let mut accepted_data = serialize(&link);
+        let (mut lamports, mut pending_lamports, mut accepted_lamports) = (0, 0, 0);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        let pending = AccountInfo::new(&member_key, false, false, &mut pending_lamports, &mut pending_data, &owner, false, 0);
+        let accepted = AccountInfo::new(&member_key, false, false, &mut accepted_lamports, &mut accepted_data, &owner, false, 0);
+
+        assert!(!is_entitled(&member_wallet, &plan, &info, None, NOW).unwrap());
+        assert!(!is_entitled(&member_wallet, &plan, &info, Some(&pending), NOW).unwrap());
+        assert!(is_entitled(&member_wallet, &plan, &info, Some(&accepted), NOW).unwrap());
+        assert!(require_entitled(&Pubkey::new_unique(), &plan, &info, Some(&accepted), NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_accounts_from_other_programs() {
+        let (key, owner, wallet, plan) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = serialize(&subscription(wallet, plan));
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        assert!(is_entitled(&wallet, &plan, &info, None, NOW).is_err());
+    }
+}
@@ -39,11 +39,21 @@ pub struct CreateSubscriptionPlan<'info> {
     #[account(
         mut,
         constraint = subscription_manager.authority != Pubkey::default() @ SubscriptionError::Unauthorized,
+        constraint = !subscription_manager.is_paused(PAUSE_PLAN_CREATION) @ SubscriptionError::ProgramPaused,
         seeds = [SUBSCRIPTION_MANAGER_SEED],
         bump = subscription_manager.bump
     )]
     pub subscription_manager: Account<'info, SubscriptionManager>,
     
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = 8 + Provider::INIT_SPACE,
+        seeds = [PROVIDER_SEED, provider.key().as_ref()],
+        bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
     #[account(
         init,
         payer = provider,
@@ -61,9 +71,40 @@ pub struct CreateSubscriptionPlan<'info> {
     )]
     pub payment_token_mint: AccountInfo<'info>,
     
+    /// Required while the manager's mint allowlist is enabled
+    #[account(
+        seeds = [ALLOWED_MINT_SEED, payment_token_mint.key().as_ref()],
+        bump = allowed_mint.bump
+    )]
+    pub allowed_mint: Option<Account<'info, AllowedMint>>,
+    
     #[account(mut)]
     pub provider: Signer<'info>,
     
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
+
+#[derive(Accounts)]
+pub struct RegisterProvider<'info> {
+    #[account(
+        init,
+        payer = provider,
+        space = 8 + Provider::INIT_SPACE,
+        seeds = [PROVIDER_SEED, provider.key().as_ref()],
+        bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::state::*;
+use crate::error::*;
+use crate::util::constants::*;
+
+#[derive(Accounts)]
+pub struct UpdateManager<'info> {
+    #[account(
+        mut,
+        has_one = authority @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = subscription_manager.pending_authority == Some(new_authority.key()) @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddAllowedMint<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AllowedMint::INIT_SPACE,
+        seeds = [ALLOWED_MINT_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    #[account(
+        has_one = authority @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowedMint<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [ALLOWED_MINT_SEED, allowed_mint.mint.as_ref()],
+        bump = allowed_mint.bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    #[account(
+        has_one = authority @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddVerifier<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Verifier::INIT_SPACE,
+        seeds = [VERIFIER_SEED, verifier.key().as_ref()],
+        bump
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+
+    #[account(
+        has_one = authority @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    /// CHECK: Any key can be delegated verification rights
+    pub verifier: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveVerifier<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [VERIFIER_SEED, verifier_account.verifier.as_ref()],
+        bump = verifier_account.bump
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+
+    #[account(
+        has_one = authority @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyProvider<'info> {
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, provider_account.authority.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+
+    #[account(
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    /// Required unless the verifier is the manager authority
+    #[account(
+        seeds = [VERIFIER_SEED, verifier.key().as_ref()],
+        bump = verifier_account.bump
+    )]
+    pub verifier_account: Option<Account<'info, Verifier>>,
+
+    #[account(
+        constraint = verifier.key() == subscription_manager.authority
+            || verifier_account.is_some() @ SubscriptionError::Unauthorized
+    )]
+    pub verifier: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateManager<'info> {
+    /// CHECK: Accounts from older versions are too short to load as `SubscriptionManager`;
+    /// the handler checks the discriminator
+    #[account(mut, owner = crate::ID, seeds = [SUBSCRIPTION_MANAGER_SEED], bump)]
+    pub subscription_manager: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
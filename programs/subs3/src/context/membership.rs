@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use crate::state::*;
+use crate::error::*;
+use crate::util::constants::*;
+
+#[derive(Accounts)]
+pub struct EnableMembershipToken<'info> {
+    #[account(
+        mut,
+        constraint = subscription_plan.owner == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    /// CHECK: Created and initialized as a Token-2022 mint by the handler
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintMembershipToken<'info> {
+    #[account(
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        constraint = subscription_plan.membership_mint == Some(membership_mint.key()) @ SubscriptionError::MembershipTokenDisabled,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        mut,
+        mint::token_program = token_2022_program,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = membership_mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_2022_program
+    )]
+    pub membership_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Holder of the membership token, checked against the subscription
+    pub subscriber: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BurnMembershipToken<'info> {
+    #[account(
+        constraint = subscription_plan.membership_mint == Some(membership_mint.key()) @ SubscriptionError::MembershipTokenDisabled,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    /// CHECK: The holder's subscription PDA; empty if it was closed by a transfer
+    #[account(
+        seeds = [SUBSCRIPTION_SEED, membership_token_account.owner.as_ref(), subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub subscription: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        mint::token_program = token_2022_program,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = membership_mint,
+        token::token_program = token_2022_program
+    )]
+    pub membership_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_2022_program: Program<'info, Token2022>,
+}
@@ -1,7 +1,15 @@
 pub mod initialize;
 pub mod subscription;
 pub mod payment;
+pub mod plan;
+pub mod manager;
+pub mod organization;
+pub mod membership;
 
 pub use initialize::*;
 pub use subscription::*;
 pub use payment::*;
+pub use plan::*;
+pub use manager::*;
+pub use organization::*;
+pub use membership::*;
@@ -0,0 +1,250 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::state::*;
+use crate::error::*;
+use crate::util::constants::*;
+
+#[derive(Accounts)]
+#[instruction(org_id: String)]
+pub struct CreateOrganization<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Organization::INIT_SPACE,
+        seeds = [ORGANIZATION_SEED, admin.key().as_ref(), org_id.as_bytes()],
+        bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = payment_token_mint,
+        token::authority = organization,
+        seeds = [ORGANIZATION_VAULT_SEED, organization.key().as_ref()],
+        bump
+    )]
+    pub organization_vault: Account<'info, TokenAccount>,
+
+    pub payment_token_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddOrganizationMember<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + OrganizationMember::INIT_SPACE,
+        seeds = [ORGANIZATION_MEMBER_SEED, organization.key().as_ref(), member.key().as_ref()],
+        bump
+    )]
+    pub organization_member: Account<'info, OrganizationMember>,
+
+    #[account(
+        mut,
+        has_one = admin @ SubscriptionError::Unauthorized,
+        seeds = [ORGANIZATION_SEED, admin.key().as_ref(), organization.org_id.as_bytes()],
+        bump = organization.bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    /// CHECK: Any wallet can be added as a member
+    pub member: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOrganizationMember<'info> {
+    #[account(
+        mut,
+        has_one = organization,
+        seeds = [ORGANIZATION_MEMBER_SEED, organization.key().as_ref(), organization_member.member.as_ref()],
+        bump = organization_member.bump
+    )]
+    pub organization_member: Account<'info, OrganizationMember>,
+
+    #[account(
+        has_one = admin @ SubscriptionError::Unauthorized,
+        seeds = [ORGANIZATION_SEED, admin.key().as_ref(), organization.org_id.as_bytes()],
+        bump = organization.bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveOrganizationMember<'info> {
+    #[account(
+        mut,
+        close = admin,
+        has_one = organization,
+        seeds = [ORGANIZATION_MEMBER_SEED, organization.key().as_ref(), organization_member.member.as_ref()],
+        bump = organization_member.bump
+    )]
+    pub organization_member: Account<'info, OrganizationMember>,
+
+    #[account(
+        mut,
+        has_one = admin @ SubscriptionError::Unauthorized,
+        seeds = [ORGANIZATION_SEED, admin.key().as_ref(), organization.org_id.as_bytes()],
+        bump = organization.bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawOrganizationFunds<'info> {
+    #[account(
+        has_one = admin @ SubscriptionError::Unauthorized,
+        seeds = [ORGANIZATION_SEED, admin.key().as_ref(), organization.org_id.as_bytes()],
+        bump = organization.bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        mut,
+        seeds = [ORGANIZATION_VAULT_SEED, organization.key().as_ref()],
+        bump
+    )]
+    pub organization_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = admin_token_account.owner == admin.key() @ SubscriptionError::Unauthorized,
+        constraint = admin_token_account.mint == organization.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AssignOrganizationSubscription<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        constraint = subscription_plan.payment_token == organization.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        has_one = admin @ SubscriptionError::Unauthorized,
+        seeds = [ORGANIZATION_SEED, admin.key().as_ref(), organization.org_id.as_bytes()],
+        bump = organization.bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        has_one = organization,
+        seeds = [ORGANIZATION_MEMBER_SEED, organization.key().as_ref(), subscriber.key().as_ref()],
+        bump = organization_member.bump
+    )]
+    pub organization_member: Account<'info, OrganizationMember>,
+
+    pub subscriber: Signer<'info>,
+
+    /// The organization approves every subscription it pays for
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnassignOrganizationSubscription<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    pub subscriber: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessOrganizationPayment<'info> {
+    #[account(
+        mut,
+        has_one = subscription_plan,
+        constraint = subscription.organization == Some(organization.key()) @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(mut)]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+
+    #[account(
+        mut,
+        seeds = [ORGANIZATION_SEED, organization.admin.as_ref(), organization.org_id.as_bytes()],
+        bump = organization.bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        mut,
+        has_one = organization,
+        seeds = [ORGANIZATION_MEMBER_SEED, organization.key().as_ref(), subscription.subscriber.as_ref()],
+        bump = organization_member.bump
+    )]
+    pub organization_member: Account<'info, OrganizationMember>,
+
+    #[account(
+        mut,
+        seeds = [ORGANIZATION_VAULT_SEED, organization.key().as_ref()],
+        bump
+    )]
+    pub organization_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_PAYMENTS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    pub token_program: Program<'info, Token>,
+}
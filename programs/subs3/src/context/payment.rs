@@ -18,11 +18,17 @@ pub struct ProcessPayment<'info> {
     #[account(mut)]
     pub subscription_plan: Account<'info, SubscriptionPlan>,
     
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
     #[account(
         mut,
         constraint = subscriber_token_account.owner == subscriber.key(),
-        constraint = subscriber_token_account.mint == subscription_plan.payment_token,
-        constraint = subscriber_token_account.amount >= subscription_plan.price_per_period @ SubscriptionError::InsufficientFunds
+        constraint = subscriber_token_account.mint == subscription_plan.payment_token
     )]
     pub subscriber_token_account: Account<'info, TokenAccount>,
     
@@ -37,25 +43,379 @@ pub struct ProcessPayment<'info> {
     )]
     pub provider_vault: Account<'info, TokenAccount>,
     
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_PAYMENTS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    /// Keeper token account receiving the plan's crank reward, if any
+    #[account(
+        mut,
+        constraint = cranker_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub cranker_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Optional receipt for this payment, paid for by the subscriber
+    #[account(
+        init,
+        payer = subscriber,
+        space = 8 + Receipt::INIT_SPACE,
+        seeds = [RECEIPT_SEED, subscription.key().as_ref(), &subscription.payment_nonce.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Option<Account<'info, Receipt>>,
+    
+    #[account(mut)]
     pub subscriber: Signer<'info>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessPaymentsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_PAYMENTS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    /// Keeper token account receiving the plan's crank reward, if any
+    #[account(
+        mut,
+        constraint = cranker_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub cranker_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseReceipt<'info> {
+    #[account(
+        mut,
+        close = subscriber,
+        has_one = subscriber,
+        seeds = [RECEIPT_SEED, receipt.subscription.as_ref(), &receipt.payment_nonce.to_le_bytes()],
+        bump = receipt.bump
+    )]
+    pub receipt: Account<'info, Receipt>,
+    
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReportUsage<'info> {
+    #[account(
+        mut,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    /// Provider account of the plan owner, holding delegated roles
+    #[account(
+        constraint = provider_account.is_authorized(provider.key(), ProviderRole::Operator) @ SubscriptionError::Unauthorized,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    /// Plan owner or operator
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Prepay<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(mut)]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key(),
+        constraint = subscriber_token_account.mint == subscription_plan.payment_token
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_PAYMENTS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    pub subscriber: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateQuantity<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(mut)]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key(),
+        constraint = subscriber_token_account.mint == subscription_plan.payment_token
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_PAYMENTS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    pub subscriber: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ForceCancelSubscription<'info> {
+    #[account(
+        mut,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        constraint = subscription_plan.owner == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, provider.key().as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        seeds = [BLOCKED_SUBSCRIBER_SEED, provider.key().as_ref(), subscription.subscriber.as_ref()],
+        bump = blocked_subscriber.bump
+    )]
+    pub blocked_subscriber: Account<'info, BlockedSubscriber>,
+    
+    /// Receives the unused value the subscriber paid for, required when there is any
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidRefundAccount,
+        constraint = subscriber_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub subscriber_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Organization vault or gifter token account receiving the unused value they paid
+    /// for, required when there is any
+    #[account(
+        mut,
+        constraint = subscription.is_sponsor_refund_account(&sponsor_token_account.key(), &sponsor_token_account.owner) @ SubscriptionError::InvalidRefundAccount,
+        constraint = sponsor_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub sponsor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_CANCELLATIONS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    pub provider: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProviderCancelSubscription<'info> {
+    #[account(
+        mut,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        constraint = subscription_plan.owner == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, provider.key().as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    /// Receives the unused value the subscriber paid for, required when there is any
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidRefundAccount,
+        constraint = subscriber_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub subscriber_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Organization vault or gifter token account receiving the unused value they paid
+    /// for, required when there is any
+    #[account(
+        mut,
+        constraint = subscription.is_sponsor_refund_account(&sponsor_token_account.key(), &sponsor_token_account.owner) @ SubscriptionError::InvalidRefundAccount,
+        constraint = sponsor_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
+    )]
+    pub sponsor_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_CANCELLATIONS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    pub provider: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
 pub struct WithdrawFunds<'info> {
     #[account(
-        has_one = provider,
         constraint = subscription_plan.is_active @ SubscriptionError::PlanInactive,
-        seeds = [SUBSCRIPTION_PLAN_SEED, provider.key().as_ref(), subscription_plan.plan_id.as_bytes()],
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
         bump = subscription_plan.bump
     )]
     pub subscription_plan: Account<'info, SubscriptionPlan>,
     
+    /// Provider account of the plan owner, holding delegated roles
+    #[account(
+        constraint = provider_account.is_authorized(provider.key(), ProviderRole::Treasurer) @ SubscriptionError::Unauthorized,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
     #[account(
         mut,
         seeds = [
             PROVIDER_VAULT_SEED,
-            provider.key().as_ref(),
+            subscription_plan.provider.as_ref(),
             subscription_plan.plan_id.as_bytes()
         ],
         bump
@@ -64,11 +424,19 @@ pub struct WithdrawFunds<'info> {
     
     #[account(
         mut,
-        constraint = provider_token_account.owner == provider.key() @ SubscriptionError::Unauthorized,
+        constraint = provider_token_account.owner == subscription_plan.owner @ SubscriptionError::Unauthorized,
         constraint = provider_token_account.mint == subscription_plan.payment_token @ SubscriptionError::InvalidTokenMint
     )]
     pub provider_token_account: Account<'info, TokenAccount>,
     
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_WITHDRAWALS) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    /// Plan owner or treasurer; funds always go to the owner's token account
     pub provider: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
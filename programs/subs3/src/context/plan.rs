@@ -0,0 +1,215 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::*;
+use crate::util::constants::*;
+
+#[derive(Accounts)]
+pub struct UpdateSubscriptionPlan<'info> {
+    #[account(
+        mut,
+        constraint = subscription_plan.owner == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCrankReward<'info> {
+    #[account(
+        mut,
+        constraint = subscription_plan.owner == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlanDetails<'info> {
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        constraint = provider_account.is_authorized(provider.key(), ProviderRole::Operator) @ SubscriptionError::Unauthorized,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+
+    /// Plan owner or operator
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptPlanOwnership<'info> {
+    #[account(
+        mut,
+        constraint = subscription_plan.pending_owner == Some(new_owner.key()) @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = previous_provider_account.bump
+    )]
+    pub previous_provider_account: Account<'info, Provider>,
+
+    #[account(
+        init_if_needed,
+        payer = new_owner,
+        space = 8 + Provider::INIT_SPACE,
+        seeds = [PROVIDER_SEED, new_owner.key().as_ref()],
+        bump
+    )]
+    pub new_provider_account: Account<'info, Provider>,
+
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+
+    #[account(mut)]
+    pub new_owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProvider<'info> {
+    #[account(
+        mut,
+        constraint = provider_account.authority == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [PROVIDER_SEED, provider.key().as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(add_on_id: String)]
+pub struct CreateAddOn<'info> {
+    #[account(
+        init,
+        payer = provider,
+        space = 8 + AddOn::INIT_SPACE,
+        seeds = [ADD_ON_SEED, subscription_plan.key().as_ref(), add_on_id.as_bytes()],
+        bump
+    )]
+    pub add_on: Account<'info, AddOn>,
+
+    #[account(
+        constraint = subscription_plan.owner == provider.key() @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BlockSubscriber<'info> {
+    #[account(
+        init,
+        payer = provider,
+        space = 8 + BlockedSubscriber::INIT_SPACE,
+        seeds = [BLOCKED_SUBSCRIBER_SEED, provider_account.authority.as_ref(), subscriber.key().as_ref()],
+        bump
+    )]
+    pub blocked_subscriber: Account<'info, BlockedSubscriber>,
+
+    #[account(
+        constraint = provider_account.is_authorized(provider.key(), ProviderRole::Operator) @ SubscriptionError::Unauthorized,
+        seeds = [PROVIDER_SEED, provider_account.authority.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+
+    /// CHECK: Any wallet can be blocked
+    pub subscriber: UncheckedAccount<'info>,
+
+    /// Provider or operator
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockSubscriber<'info> {
+    #[account(
+        mut,
+        close = provider,
+        seeds = [BLOCKED_SUBSCRIBER_SEED, provider_account.authority.as_ref(), blocked_subscriber.subscriber.as_ref()],
+        bump = blocked_subscriber.bump
+    )]
+    pub blocked_subscriber: Account<'info, BlockedSubscriber>,
+
+    #[account(
+        constraint = provider_account.is_authorized(provider.key(), ProviderRole::Operator) @ SubscriptionError::Unauthorized,
+        seeds = [PROVIDER_SEED, provider_account.authority.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+
+    /// Provider or operator
+    #[account(mut)]
+    pub provider: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSubscriptionPlan<'info> {
+    /// CHECK: Accounts from older versions are too short to load as `SubscriptionPlan`;
+    /// the handler checks the discriminator and the provider
+    #[account(mut, owner = crate::ID)]
+    pub subscription_plan: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    /// CHECK: Only seeds the provider account; matched against the plan's provider
+    pub provider: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Provider::INIT_SPACE,
+        seeds = [PROVIDER_SEED, provider.key().as_ref()],
+        bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
@@ -1,4 +1,8 @@
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface;
 use crate::state::*;
 use crate::error::*;
 use crate::util::constants::*;
@@ -17,6 +21,7 @@ pub struct Subscribe<'info> {
     #[account(
         mut,
         constraint = subscription_plan.is_active @ SubscriptionError::PlanInactive,
+        constraint = subscription_plan.sunset_at.is_none() @ SubscriptionError::PlanSunset,
         seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
         bump = subscription_plan.bump
     )]
@@ -24,14 +29,142 @@ pub struct Subscribe<'info> {
     
     #[account(
         mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        constraint = !subscription_manager.is_paused(PAUSE_SUBSCRIBE) @ SubscriptionError::ProgramPaused,
         seeds = [SUBSCRIPTION_MANAGER_SEED],
         bump = subscription_manager.bump
     )]
     pub subscription_manager: Account<'info, SubscriptionManager>,
     
+    /// CHECK: Provider blocklist PDA for this subscriber, only initialized if blocked
+    #[account(
+        seeds = [BLOCKED_SUBSCRIBER_SEED, subscription_plan.owner.as_ref(), subscriber.key().as_ref()],
+        bump,
+        constraint = blocked_subscriber.data_is_empty() @ SubscriptionError::SubscriberBlocked
+    )]
+    pub blocked_subscriber: UncheckedAccount<'info>,
+    
+    /// Membership token accounts, required when the plan issues membership tokens
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        associated_token::mint = membership_mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_2022_program
+    )]
+    pub membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
     #[account(mut)]
     pub subscriber: Signer<'info>,
     
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GiftSubscription<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [SUBSCRIPTION_SEED, beneficiary.key().as_ref(), subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        constraint = subscription_plan.is_active @ SubscriptionError::PlanInactive,
+        constraint = subscription_plan.sunset_at.is_none() @ SubscriptionError::PlanSunset,
+        seeds = [SUBSCRIPTION_PLAN_SEED, subscription_plan.provider.as_ref(), subscription_plan.plan_id.as_bytes()],
+        bump = subscription_plan.bump
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        constraint = !subscription_manager.is_paused(PAUSE_SUBSCRIBE) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    /// CHECK: Provider blocklist PDA for the beneficiary, only initialized if blocked
+    #[account(
+        seeds = [BLOCKED_SUBSCRIBER_SEED, subscription_plan.owner.as_ref(), beneficiary.key().as_ref()],
+        bump,
+        constraint = blocked_subscriber.data_is_empty() @ SubscriptionError::SubscriberBlocked
+    )]
+    pub blocked_subscriber: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key(),
+        constraint = payer_token_account.mint == subscription_plan.payment_token
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    /// CHECK: Any wallet other than the plan owner can receive a gift
+    #[account(
+        constraint = beneficiary.key() != subscription_plan.owner @ SubscriptionError::Unauthorized
+    )]
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    /// Membership token accounts, required when the plan issues membership tokens
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = membership_mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_2022_program
+    )]
+    pub membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
     pub system_program: Program<'info, System>,
 }
 
@@ -51,11 +184,159 @@ pub struct CancelSubscription<'info> {
     
     #[account(
         mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        mut,
+        constraint = !subscription_manager.is_paused(PAUSE_CANCELLATIONS) @ SubscriptionError::ProgramPaused,
         seeds = [SUBSCRIPTION_MANAGER_SEED],
         bump = subscription_manager.bump
     )]
     pub subscription_manager: Account<'info, SubscriptionManager>,
     
+    /// Membership token accounts, passed to burn the subscriber's token right away
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        associated_token::mint = membership_mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_2022_program
+    )]
+    pub membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
+    pub subscriber: Signer<'info>,
+    
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[derive(Accounts)]
+pub struct Reactivate<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        mut,
+        constraint = subscription_plan.is_active @ SubscriptionError::PlanInactive,
+        constraint = subscription_plan.sunset_at.is_none() @ SubscriptionError::PlanSunset
+    )]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_SUBSCRIBE) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    /// CHECK: Provider blocklist PDA for this subscriber, only initialized if blocked
+    #[account(
+        seeds = [BLOCKED_SUBSCRIBER_SEED, subscription_plan.owner.as_ref(), subscriber.key().as_ref()],
+        bump,
+        constraint = blocked_subscriber.data_is_empty() @ SubscriptionError::SubscriberBlocked
+    )]
+    pub blocked_subscriber: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key(),
+        constraint = subscriber_token_account.mint == subscription_plan.payment_token
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [
+            PROVIDER_VAULT_SEED,
+            subscription_plan.provider.as_ref(),
+            subscription_plan.plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub provider_vault: Account<'info, TokenAccount>,
+    
+    /// Membership token accounts, required when the plan issues membership tokens
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        associated_token::mint = membership_mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_2022_program
+    )]
+    pub membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSubscription<'info> {
+    /// CHECK: Accounts from older versions are too short to load as `Subscription`;
+    /// the handler checks the discriminator
+    #[account(mut, owner = crate::ID)]
+    pub subscription: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRenewsAfterGift<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription.subscription_plan.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub subscriber: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSpendingLimits<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription.subscription_plan.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
     pub subscriber: Signer<'info>,
 }
 
@@ -88,3 +369,258 @@ pub struct ResumeSubscription<'info> {
     pub subscription_plan: Account<'info, SubscriptionPlan>,
     pub subscriber: Signer<'info>,
 }
+
+#[derive(Accounts)]
+pub struct AttachAddOn<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        has_one = subscription_plan,
+        constraint = add_on.is_active @ SubscriptionError::AddOnInactive,
+        seeds = [ADD_ON_SEED, subscription_plan.key().as_ref(), add_on.add_on_id.as_bytes()],
+        bump = add_on.bump
+    )]
+    pub add_on: Account<'info, AddOn>,
+    
+    pub subscriber: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DetachAddOn<'info> {
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    /// CHECK: Only the key is used to find the attached add-on, so detaching
+    /// still works if the add-on account is ever closed
+    pub add_on: UncheckedAccount<'info>,
+    
+    pub subscriber: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireSunsetSubscription<'info> {
+    #[account(
+        mut,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(mut)]
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    #[account(
+        mut,
+        seeds = [PROVIDER_SEED, subscription_plan.owner.as_ref()],
+        bump = provider_account.bump
+    )]
+    pub provider_account: Account<'info, Provider>,
+    
+    /// Membership token accounts, required when the plan issues membership tokens
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        constraint = membership_token_account.owner == subscription.subscriber @ SubscriptionError::Unauthorized,
+        token::mint = membership_mint,
+        token::token_program = token_2022_program
+    )]
+    pub membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[derive(Accounts)]
+pub struct TransferSubscription<'info> {
+    #[account(
+        mut,
+        close = subscriber,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [SUBSCRIPTION_SEED, recipient.key().as_ref(), subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub new_subscription: Account<'info, Subscription>,
+    
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    /// CHECK: Provider blocklist PDA for the recipient, only initialized if blocked
+    #[account(
+        seeds = [BLOCKED_SUBSCRIBER_SEED, subscription_plan.owner.as_ref(), recipient.key().as_ref()],
+        bump,
+        constraint = blocked_subscriber.data_is_empty() @ SubscriptionError::SubscriberBlocked
+    )]
+    pub blocked_subscriber: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = !subscription_manager.is_paused(PAUSE_SUBSCRIBE) @ SubscriptionError::ProgramPaused,
+        seeds = [SUBSCRIPTION_MANAGER_SEED],
+        bump = subscription_manager.bump
+    )]
+    pub subscription_manager: Account<'info, SubscriptionManager>,
+    
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = recipient.key() != subscription_plan.owner @ SubscriptionError::Unauthorized
+    )]
+    pub recipient: Signer<'info>,
+    
+    /// Membership token accounts, required when the plan issues membership tokens:
+    /// the subscriber's token is burned and the recipient gets a new one
+    #[account(
+        mut,
+        seeds = [MEMBERSHIP_MINT_SEED, subscription_plan.key().as_ref()],
+        bump
+    )]
+    pub membership_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        associated_token::mint = membership_mint,
+        associated_token::authority = subscriber,
+        associated_token::token_program = token_2022_program
+    )]
+    pub membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = membership_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_2022_program
+    )]
+    pub recipient_membership_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+    
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InviteMember<'info> {
+    #[account(
+        init,
+        payer = subscriber,
+        space = 8 + SubscriptionMember::INIT_SPACE,
+        seeds = [SUBSCRIPTION_MEMBER_SEED, subscription.key().as_ref(), member.key().as_ref()],
+        bump
+    )]
+    pub subscription_member: Account<'info, SubscriptionMember>,
+    
+    #[account(
+        mut,
+        has_one = subscriber,
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscriber.key().as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    /// CHECK: Any wallet can be invited
+    pub member: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptMembership<'info> {
+    #[account(
+        mut,
+        has_one = member,
+        seeds = [SUBSCRIPTION_MEMBER_SEED, subscription_member.subscription.as_ref(), member.key().as_ref()],
+        bump = subscription_member.bump
+    )]
+    pub subscription_member: Account<'info, SubscriptionMember>,
+    
+    pub member: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveMember<'info> {
+    #[account(
+        mut,
+        close = subscriber,
+        has_one = subscription,
+        constraint = authority.key() == subscription.subscriber
+            || authority.key() == subscription_member.member @ SubscriptionError::Unauthorized,
+        seeds = [SUBSCRIPTION_MEMBER_SEED, subscription.key().as_ref(), subscription_member.member.as_ref()],
+        bump = subscription_member.bump
+    )]
+    pub subscription_member: Account<'info, SubscriptionMember>,
+    
+    #[account(
+        mut,
+        has_one = subscriber,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription.subscription_plan.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    /// CHECK: Receives the member account rent, checked against the subscription
+    #[account(mut)]
+    pub subscriber: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyEntitlement<'info> {
+    #[account(
+        has_one = subscription_plan,
+        seeds = [SUBSCRIPTION_SEED, subscription.subscriber.as_ref(), subscription_plan.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    
+    pub subscription_plan: Account<'info, SubscriptionPlan>,
+    
+    /// Only needed when `wallet` is a group plan member rather than the subscriber
+    #[account(
+        seeds = [SUBSCRIPTION_MEMBER_SEED, subscription.key().as_ref(), wallet.key().as_ref()],
+        bump = subscription_member.bump
+    )]
+    pub subscription_member: Option<Account<'info, SubscriptionMember>>,
+    
+    /// CHECK: Wallet whose access is being checked; does not need to sign
+    pub wallet: UncheckedAccount<'info>,
+}
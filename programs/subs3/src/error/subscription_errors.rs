@@ -32,4 +32,104 @@ pub enum SubscriptionError {
     SubscriptionAlreadyExists,
     #[msg("Unauthorized access - invalid authority")]
     Unauthorized,
+    #[msg("Invalid prepay bundle")]
+    InvalidPrepayBundle,
+    #[msg("Too many prepay bundles")]
+    TooManyPrepayBundles,
+    #[msg("Subscription is prepaid - payment is not due yet")]
+    SubscriptionPrepaid,
+    #[msg("Subscription plan has no metered component")]
+    PlanNotMetered,
+    #[msg("Invalid usage - units must be greater than 0")]
+    InvalidUsage,
+    #[msg("Invalid pricing schedule - tiers must increase and end with an unbounded tier")]
+    InvalidPricingSchedule,
+    #[msg("Invalid quantity - must be greater than 0 and different from the current quantity")]
+    InvalidQuantity,
+    #[msg("Add-on is inactive")]
+    AddOnInactive,
+    #[msg("Add-on is already attached to this subscription")]
+    AddOnAlreadyAttached,
+    #[msg("Add-on is not attached to this subscription")]
+    AddOnNotAttached,
+    #[msg("Subscription has reached the maximum number of add-ons")]
+    TooManyAddOns,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("This operation is paused by the program authority")]
+    ProgramPaused,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Payment token mint is not on the allowlist")]
+    MintNotAllowed,
+    #[msg("Metadata URI is too long")]
+    MetadataUriTooLong,
+    #[msg("Invalid verification level")]
+    InvalidVerificationLevel,
+    #[msg("Verification expiry must be in the future")]
+    InvalidVerificationExpiry,
+    #[msg("Provider is not verified")]
+    ProviderNotVerified,
+    #[msg("Subscriber is blocked by this provider")]
+    SubscriberBlocked,
+    #[msg("Subscription plan is being sunset")]
+    PlanSunset,
+    #[msg("Sunset date must give subscribers the minimum notice")]
+    InvalidSunsetDate,
+    #[msg("Subscription plan has not reached its sunset date")]
+    PlanNotSunset,
+    #[msg("No plan ownership transfer is pending")]
+    NoPendingOwner,
+    #[msg("Provider has reached the maximum number of roles")]
+    TooManyRoles,
+    #[msg("Role assignment not found")]
+    RoleNotFound,
+    #[msg("Gift must cover at least one period")]
+    InvalidGiftPeriods,
+    #[msg("Gifted periods have ended and the subscription does not renew")]
+    GiftEnded,
+    #[msg("Subscription is paid by an organization")]
+    SubscriptionSponsored,
+    #[msg("Organization member spending cap exceeded")]
+    SpendingCapExceeded,
+    #[msg("All member slots on this subscription are in use")]
+    MemberSlotsFull,
+    #[msg("Subscribers cannot invite themselves")]
+    CannotInviteSelf,
+    #[msg("Membership has already been accepted")]
+    MembershipAlreadyAccepted,
+    #[msg("Remove all members before transferring the subscription")]
+    SubscriptionHasMembers,
+    #[msg("Wallet is not entitled to this subscription plan")]
+    NotEntitled,
+    #[msg("Plan does not issue membership tokens")]
+    MembershipTokenDisabled,
+    #[msg("Plan already issues membership tokens")]
+    MembershipTokenAlreadyEnabled,
+    #[msg("Membership token accounts are required for this plan")]
+    MembershipAccountsMissing,
+    #[msg("Wallet already holds a membership token")]
+    MembershipTokenHeld,
+    #[msg("Subscription is still entitled to its membership token")]
+    MembershipStillEntitled,
+    #[msg("Token account holds no membership token")]
+    NoMembershipToken,
+    #[msg("Account is not a subscription")]
+    InvalidSubscriptionAccount,
+    #[msg("Subscription account is already up to date")]
+    SubscriptionUpToDate,
+    #[msg("Batch must be non-empty triples of subscription, token account and payment delegate")]
+    InvalidBatch,
+    #[msg("Crank reward is outside the protocol bounds")]
+    InvalidCrankReward,
+    #[msg("Refund must go to the account that paid for the subscription")]
+    InvalidRefundAccount,
+    #[msg("Subscription is not paid by a gift")]
+    NotAGift,
+    #[msg("Subscription is still active")]
+    SubscriptionStillActive,
+    #[msg("Account is not of the type being migrated")]
+    InvalidMigrationAccount,
+    #[msg("Account is already up to date")]
+    AccountUpToDate,
 }
@@ -1,4 +1,111 @@
 use anchor_lang::prelude::*;
+use crate::state::{AttachedAddOn, BatchPaymentStatus, CrankReward, PricingModel, ProviderRole, SpendingLimitKind};
+
+#[event]
+pub struct AuthorityProposed {
+    pub authority: Pubkey,
+    pub proposed_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityAccepted {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityProposalCancelled {
+    pub authority: Pubkey,
+    pub cancelled_authority: Pubkey,
+}
+
+#[event]
+pub struct PauseStateUpdated {
+    pub authority: Pubkey,
+    pub paused: bool,
+    pub pause_flags: u8,
+    pub allow_exits_when_paused: bool,
+}
+
+#[event]
+pub struct MintAllowlistModeUpdated {
+    pub authority: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct MintAllowed {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct MintRemoved {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct ProviderRegistered {
+    pub provider: Pubkey,
+    pub name: String,
+    pub metadata_uri: String,
+}
+
+#[event]
+pub struct ProviderUpdated {
+    pub provider: Pubkey,
+    pub name: String,
+    pub metadata_uri: String,
+}
+
+#[event]
+pub struct VerifierAdded {
+    pub authority: Pubkey,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct VerifierRemoved {
+    pub authority: Pubkey,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct ProviderVerified {
+    pub provider: Pubkey,
+    pub verified_by: Pubkey,
+    pub verification_level: u8,
+    pub expires_at: Option<i64>,
+}
+
+#[event]
+pub struct ProviderVerificationRevoked {
+    pub provider: Pubkey,
+    pub revoked_by: Pubkey,
+}
+
+#[event]
+pub struct ProviderRoleAdded {
+    pub provider: Pubkey,
+    pub member: Pubkey,
+    pub role: ProviderRole,
+}
+
+#[event]
+pub struct ProviderRoleRemoved {
+    pub provider: Pubkey,
+    pub member: Pubkey,
+    pub role: ProviderRole,
+}
+
+#[event]
+pub struct PlanDetailsUpdated {
+    pub subscription_plan: Pubkey,
+    pub updated_by: Pubkey,
+    pub name: String,
+    pub description: String,
+}
 
 #[event]
 pub struct SubscriptionPlanCreated {
@@ -14,7 +121,26 @@ pub struct SubscriptionCreated {
     pub subscriber: Pubkey,
     pub subscription_plan: Pubkey,
     pub start_time: i64,
-    pub next_payment_due: i64
+    pub next_payment_due: i64,
+    pub quantity: u32,
+}
+
+#[event]
+pub struct SubscriptionGifted {
+    /// `None` when the gifter chose to stay anonymous
+    pub gifter: Option<Pubkey>,
+    pub beneficiary: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub periods: u16,
+    pub amount: u64,
+    pub paid_through: i64,
+}
+
+#[event]
+pub struct GiftRenewalUpdated {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub renews_after_gift: bool,
 }
 
 #[event]
@@ -22,11 +148,310 @@ pub struct PaymentProcessed {
     pub subscriber: Pubkey,
     pub subscription_plan: Pubkey,
     pub amount: u64,
+    pub base_amount: u64,
+    pub usage_units: u64,
+    pub usage_amount: u64,
+    pub credit_applied: u64,
+    pub add_on_amount: u64,
+    pub add_ons: Vec<AttachedAddOn>,
     pub payment_number: u32,
     pub payment_nonce: u64,
     pub next_payment_due: i64,
 }
 
+#[event]
+pub struct PrepayBundlesUpdated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub bundle_count: u8,
+}
+
+#[event]
+pub struct SubscriptionPrepaid {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub periods: u16,
+    pub amount: u64,
+    pub payment_nonce: u64,
+    pub prepaid_through: i64,
+}
+
+#[event]
+pub struct MeteredComponentUpdated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub unit_price: u64,
+    pub included_units: u64,
+    pub overage_cap: Option<u64>,
+}
+
+#[event]
+pub struct PricingScheduleUpdated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub model: Option<PricingModel>,
+    pub tier_count: u8,
+}
+
+#[event]
+pub struct UsageReported {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub units: u64,
+    pub current_period_usage: u64,
+    pub next_payment_due: i64,
+}
+
+#[event]
+pub struct QuantityUpdated {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub old_quantity: u32,
+    pub new_quantity: u32,
+    pub prorated_charge: u64,
+    pub credit_added: u64,
+}
+
+#[event]
+pub struct AddOnCreated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub add_on: Pubkey,
+    pub add_on_id: String,
+    pub price_per_period: u64,
+}
+
+#[event]
+pub struct AddOnAttached {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub add_on: Pubkey,
+    pub price_per_period: u64,
+}
+
+#[event]
+pub struct AddOnDetached {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub add_on: Pubkey,
+}
+
+#[event]
+pub struct SubscriptionTransferred {
+    pub subscription_plan: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub subscription: Pubkey,
+    pub transferred_at: i64,
+}
+
+#[event]
+pub struct OrganizationCreated {
+    pub organization: Pubkey,
+    pub admin: Pubkey,
+    pub org_id: String,
+    pub payment_token: Pubkey,
+}
+
+#[event]
+pub struct OrganizationMemberAdded {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub spending_cap: Option<u64>,
+}
+
+#[event]
+pub struct OrganizationMemberUpdated {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub spending_cap: Option<u64>,
+}
+
+#[event]
+pub struct OrganizationMemberRemoved {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+}
+
+#[event]
+pub struct OrganizationSubscriptionAssigned {
+    pub organization: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+}
+
+#[event]
+pub struct OrganizationSubscriptionUnassigned {
+    pub organization: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+}
+
+#[event]
+pub struct OrganizationPaymentProcessed {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub amount: u64,
+    pub member_total_spent: u64,
+    pub organization_total_spent: u64,
+}
+
+#[event]
+pub struct OrganizationFundsWithdrawn {
+    pub organization: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MaxMembersUpdated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub max_members: u16,
+}
+
+#[event]
+pub struct MemberInvited {
+    pub subscription: Pubkey,
+    pub subscriber: Pubkey,
+    pub member: Pubkey,
+    pub member_count: u16,
+}
+
+#[event]
+pub struct MembershipAccepted {
+    pub subscription: Pubkey,
+    pub member: Pubkey,
+    pub accepted_at: i64,
+}
+
+#[event]
+pub struct MemberRemoved {
+    pub subscription: Pubkey,
+    pub member: Pubkey,
+    pub removed_by: Pubkey,
+    pub member_count: u16,
+}
+
+#[event]
+pub struct MembershipTokenEnabled {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct MembershipTokenMinted {
+    pub subscription_plan: Pubkey,
+    pub holder: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct MembershipTokenBurned {
+    pub subscription_plan: Pubkey,
+    pub holder: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ReceiptCreated {
+    pub receipt: Pubkey,
+    pub subscription: Pubkey,
+    pub payment_nonce: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ReceiptClosed {
+    pub receipt: Pubkey,
+    pub subscription: Pubkey,
+    pub payment_nonce: u64,
+}
+
+#[event]
+pub struct SubscriptionMigrated {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub old_len: u32,
+    pub new_len: u32,
+}
+
+#[event]
+pub struct SubscriptionPlanMigrated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub old_len: u32,
+    pub new_len: u32,
+}
+
+#[event]
+pub struct ManagerMigrated {
+    pub old_len: u32,
+    pub new_len: u32,
+}
+
+#[event]
+pub struct SpendingLimitsUpdated {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub max_per_charge: Option<u64>,
+    pub max_per_window: Option<u64>,
+    pub authorized_until: Option<i64>,
+}
+
+#[event]
+pub struct PaymentBlockedByLimit {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub amount: u64,
+    pub limit: SpendingLimitKind,
+    pub spent_in_window: u64,
+}
+
+#[event]
+pub struct BatchPaymentResult {
+    pub subscription: Pubkey,
+    pub subscriber: Pubkey,
+    pub index: u16,
+    pub status: BatchPaymentStatus,
+    pub amount: u64,
+    /// Compute units used by this item, not counting the event itself
+    pub compute_units: u64,
+}
+
+#[event]
+pub struct BatchPaymentsProcessed {
+    pub subscription_plan: Pubkey,
+    pub items: u16,
+    pub paid: u16,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct CrankRewardBoundsUpdated {
+    pub authority: Pubkey,
+    pub min_crank_reward: u64,
+    pub max_crank_reward: u64,
+}
+
+#[event]
+pub struct CrankRewardUpdated {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub crank_reward: Option<CrankReward>,
+}
+
+#[event]
+pub struct CrankRewardPaid {
+    pub subscription_plan: Pubkey,
+    pub subscriber: Pubkey,
+    pub cranker: Pubkey,
+    pub amount: u64,
+}
+
 #[event]
 pub struct SubscriptionCancelled {
     pub subscriber: Pubkey,
@@ -34,6 +459,87 @@ pub struct SubscriptionCancelled {
     pub cancelled_at: i64,
 }
 
+#[event]
+pub struct SubscriptionReactivated {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub quantity: u32,
+    pub amount: u64,
+    pub next_payment_due: i64,
+}
+
+#[event]
+pub struct SubscriberBlocked {
+    pub provider: Pubkey,
+    pub subscriber: Pubkey,
+}
+
+#[event]
+pub struct SubscriberUnblocked {
+    pub provider: Pubkey,
+    pub subscriber: Pubkey,
+}
+
+#[event]
+pub struct SubscriptionForceCancelled {
+    pub provider: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    /// Refunded to the subscriber
+    pub refund_amount: u64,
+    /// Refunded to the organization or gifter that paid for the period in progress
+    pub sponsor_refund_amount: u64,
+    pub cancelled_at: i64,
+}
+
+#[event]
+pub struct SubscriptionCancelledByProvider {
+    pub provider: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    /// Refunded to the subscriber
+    pub refund_amount: u64,
+    /// Refunded to the organization or gifter that paid for the period in progress
+    pub sponsor_refund_amount: u64,
+    pub cancelled_at: i64,
+}
+
+#[event]
+pub struct PlanSunsetScheduled {
+    pub provider: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub sunset_at: i64,
+}
+
+#[event]
+pub struct SubscriptionSunset {
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    pub sunset_at: i64,
+    pub ended_at: i64,
+}
+
+#[event]
+pub struct PlanOwnershipProposed {
+    pub subscription_plan: Pubkey,
+    pub owner: Pubkey,
+    pub proposed_owner: Pubkey,
+}
+
+#[event]
+pub struct PlanOwnershipTransferred {
+    pub subscription_plan: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct PlanOwnershipProposalCancelled {
+    pub subscription_plan: Pubkey,
+    pub owner: Pubkey,
+    pub cancelled_owner: Pubkey,
+}
+
 #[event]
 pub struct SubscriptionFundsWithdrawn {
     pub provider: Pubkey,
@@ -1,22 +1,37 @@
+// anchor 0.31 #[program] emits its IDL instructions at the crate root, and they call the
+// deprecated AccountInfo::realloc. A lint level can't be attached to that generated code
+// alone, so deprecations are allowed crate-wide and turned back on for our own modules.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer};
+use anchor_spl::token::{self, TokenAccount, Transfer};
+use anchor_lang::solana_program::compute_units::sol_remaining_compute_units;
+use anchor_lang::solana_program::program_option::COption;
 
 declare_id!("4vWTjxHPJx6YzWeMfmimgeA2cMcyC9kXg3jqCq93mmo2");
 
 // Module declarations
+#[warn(deprecated)]
 pub mod state;
+#[warn(deprecated)]
 pub mod context;
+#[warn(deprecated)]
 pub mod event;
+#[warn(deprecated)]
 pub mod error;
+#[warn(deprecated)]
 pub mod util;
 
 // Re-exports for convenience
-pub use state::{SubscriptionManager, SubscriptionPlan, Subscription};
+pub use state::{SubscriptionManager, SubscriptionPlan, Subscription, PaymentRecord, SpendingLimits, SpendingLimitKind, BatchPaymentStatus, Sponsor, PrepayBundle, MeteredComponent};
+pub use state::{CrankReward, PricingModel, PricingTier, PricingSchedule, AddOn, AttachedAddOn, AllowedMint, Provider, Verifier, BlockedSubscriber};
+pub use state::{ProviderRole, RoleAssignment, Organization, OrganizationMember, SubscriptionMember, Receipt};
 pub use context::*;
 pub use event::*;
 pub use error::*;
 pub use util::*;
 
+#[warn(deprecated)]
 #[program]
 pub mod subs3 {
     use super::*;
@@ -28,11 +43,337 @@ pub mod subs3 {
         manager.total_providers = 0;
         manager.total_subscriptions = 0;
         manager.bump = ctx.bumps.subscription_manager;
+        manager.total_plans = 0;
+        manager.pending_authority = None;
+        manager.paused = false;
+        manager.pause_flags = 0;
+        manager.allow_exits_when_paused = true;
+        manager.mint_allowlist_enabled = false;
+        manager.min_crank_reward = 0;
+        manager.max_crank_reward = 0;
         
         msg!("Subscription manager initialized with authority: {}", manager.authority);
         Ok(())
     }
 
+    /// Propose a new manager authority (Authority function)
+    /// The transfer only takes effect once the proposed key calls `accept_authority`
+    pub fn propose_authority(ctx: Context<UpdateManager>, new_authority: Pubkey) -> Result<()> {
+        require!(new_authority != Pubkey::default(), SubscriptionError::Unauthorized);
+
+        let manager = &mut ctx.accounts.subscription_manager;
+        manager.pending_authority = Some(new_authority);
+
+        emit!(AuthorityProposed {
+            authority: manager.authority,
+            proposed_authority: new_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Accept a pending authority transfer (Proposed authority function)
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let manager = &mut ctx.accounts.subscription_manager;
+        let previous_authority = manager.authority;
+
+        manager.authority = ctx.accounts.new_authority.key();
+        manager.pending_authority = None;
+
+        emit!(AuthorityAccepted {
+            previous_authority,
+            new_authority: manager.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a pending authority transfer (Authority function)
+    pub fn cancel_authority_proposal(ctx: Context<UpdateManager>) -> Result<()> {
+        let manager = &mut ctx.accounts.subscription_manager;
+        let cancelled_authority = manager.pending_authority
+            .take()
+            .ok_or(SubscriptionError::NoPendingAuthority)?;
+
+        emit!(AuthorityProposalCancelled {
+            authority: manager.authority,
+            cancelled_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Set the emergency pause state (Authority function)
+    /// `paused` stops every category; `pause_flags` stops individual categories.
+    /// With `allow_exits_when_paused`, withdrawals and cancellations stay open during
+    /// a global pause so users are never trapped.
+    pub fn set_pause_state(
+        ctx: Context<UpdateManager>,
+        paused: bool,
+        pause_flags: u8,
+        allow_exits_when_paused: bool,
+    ) -> Result<()> {
+        require!(pause_flags & !PAUSE_ALL == 0, SubscriptionError::InvalidPauseFlags);
+
+        let manager = &mut ctx.accounts.subscription_manager;
+        manager.paused = paused;
+        manager.pause_flags = pause_flags;
+        manager.allow_exits_when_paused = allow_exits_when_paused;
+
+        emit!(PauseStateUpdated {
+            authority: manager.authority,
+            paused,
+            pause_flags,
+            allow_exits_when_paused,
+        });
+
+        Ok(())
+    }
+
+    /// Turn the payment mint allowlist on or off (Authority function)
+    /// While off, plans may be created with any SPL Token mint
+    pub fn set_mint_allowlist_enabled(ctx: Context<UpdateManager>, enabled: bool) -> Result<()> {
+        let manager = &mut ctx.accounts.subscription_manager;
+        manager.mint_allowlist_enabled = enabled;
+
+        emit!(MintAllowlistModeUpdated {
+            authority: manager.authority,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Set the protocol-wide bounds on crank rewards (Authority function)
+    /// Plan rewards must be configured within these bounds and are capped at the maximum
+    /// when paid out; a maximum of zero turns crank rewards off
+    pub fn set_crank_reward_bounds(
+        ctx: Context<UpdateManager>,
+        min_crank_reward: u64,
+        max_crank_reward: u64,
+    ) -> Result<()> {
+        require!(min_crank_reward <= max_crank_reward, SubscriptionError::InvalidCrankReward);
+
+        let manager = &mut ctx.accounts.subscription_manager;
+        manager.min_crank_reward = min_crank_reward;
+        manager.max_crank_reward = max_crank_reward;
+
+        emit!(CrankRewardBoundsUpdated {
+            authority: manager.authority,
+            min_crank_reward,
+            max_crank_reward,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a payment token mint for new plans (Authority function)
+    pub fn add_allowed_mint(ctx: Context<AddAllowedMint>) -> Result<()> {
+        let allowed_mint = &mut ctx.accounts.allowed_mint;
+        allowed_mint.mint = ctx.accounts.mint.key();
+        allowed_mint.added_by = ctx.accounts.authority.key();
+        allowed_mint.added_at = Clock::get()?.unix_timestamp;
+        allowed_mint.bump = ctx.bumps.allowed_mint;
+
+        emit!(MintAllowed {
+            authority: allowed_mint.added_by,
+            mint: allowed_mint.mint,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a payment token mint from the allowlist (Authority function)
+    /// Existing plans using the mint are not affected
+    pub fn remove_allowed_mint(ctx: Context<RemoveAllowedMint>) -> Result<()> {
+        emit!(MintRemoved {
+            authority: ctx.accounts.authority.key(),
+            mint: ctx.accounts.allowed_mint.mint,
+        });
+
+        Ok(())
+    }
+
+    /// Register a provider profile ahead of the first plan (Provider function)
+    pub fn register_provider(
+        ctx: Context<RegisterProvider>,
+        name: String,
+        metadata_uri: String,
+    ) -> Result<()> {
+        require!(name.len() <= MAX_NAME_LENGTH, SubscriptionError::NameTooLong);
+        require!(metadata_uri.len() <= MAX_METADATA_URI_LENGTH, SubscriptionError::MetadataUriTooLong);
+
+        let provider_account = &mut ctx.accounts.provider_account;
+        let manager = &mut ctx.accounts.subscription_manager;
+
+        provider_account.initialize(
+            ctx.accounts.provider.key(),
+            name,
+            metadata_uri,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.provider_account,
+        );
+
+        manager.total_providers = manager.total_providers
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(ProviderRegistered {
+            provider: provider_account.authority,
+            name: provider_account.name.clone(),
+            metadata_uri: provider_account.metadata_uri.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Update the provider's display name and metadata URI (Provider function)
+    pub fn update_provider(
+        ctx: Context<UpdateProvider>,
+        name: String,
+        metadata_uri: String,
+    ) -> Result<()> {
+        require!(name.len() <= MAX_NAME_LENGTH, SubscriptionError::NameTooLong);
+        require!(metadata_uri.len() <= MAX_METADATA_URI_LENGTH, SubscriptionError::MetadataUriTooLong);
+
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.name = name;
+        provider_account.metadata_uri = metadata_uri;
+
+        emit!(ProviderUpdated {
+            provider: provider_account.authority,
+            name: provider_account.name.clone(),
+            metadata_uri: provider_account.metadata_uri.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Delegate provider verification to another key (Authority function)
+    pub fn add_verifier(ctx: Context<AddVerifier>) -> Result<()> {
+        let verifier_account = &mut ctx.accounts.verifier_account;
+        verifier_account.verifier = ctx.accounts.verifier.key();
+        verifier_account.added_by = ctx.accounts.authority.key();
+        verifier_account.added_at = Clock::get()?.unix_timestamp;
+        verifier_account.bump = ctx.bumps.verifier_account;
+
+        emit!(VerifierAdded {
+            authority: verifier_account.added_by,
+            verifier: verifier_account.verifier,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a delegated verifier (Authority function)
+    /// Badges already issued by the verifier stay in place until revoked or expired
+    pub fn remove_verifier(ctx: Context<RemoveVerifier>) -> Result<()> {
+        emit!(VerifierRemoved {
+            authority: ctx.accounts.authority.key(),
+            verifier: ctx.accounts.verifier_account.verifier,
+        });
+
+        Ok(())
+    }
+
+    /// Mark a provider as verified (Authority or verifier function)
+    pub fn verify_provider(
+        ctx: Context<VerifyProvider>,
+        verification_level: u8,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        require!(
+            verification_level > 0 && verification_level <= MAX_VERIFICATION_LEVEL,
+            SubscriptionError::InvalidVerificationLevel
+        );
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                SubscriptionError::InvalidVerificationExpiry
+            );
+        }
+
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.verification_level = verification_level;
+        provider_account.verified_by = Some(ctx.accounts.verifier.key());
+        provider_account.verification_expires_at = expires_at;
+
+        emit!(ProviderVerified {
+            provider: provider_account.authority,
+            verified_by: ctx.accounts.verifier.key(),
+            verification_level,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a provider's verification badge (Authority or verifier function)
+    pub fn revoke_provider_verification(ctx: Context<VerifyProvider>) -> Result<()> {
+        let provider_account = &mut ctx.accounts.provider_account;
+        require!(provider_account.verification_level > 0, SubscriptionError::ProviderNotVerified);
+
+        provider_account.verification_level = 0;
+        provider_account.verified_by = None;
+        provider_account.verification_expires_at = None;
+
+        emit!(ProviderVerificationRevoked {
+            provider: provider_account.authority,
+            revoked_by: ctx.accounts.verifier.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Delegate a scoped role to another key (Provider function)
+    pub fn add_provider_role(
+        ctx: Context<UpdateProvider>,
+        member: Pubkey,
+        role: ProviderRole,
+    ) -> Result<()> {
+        let provider_account = &mut ctx.accounts.provider_account;
+
+        // Re-adding an existing assignment is a no-op
+        if !provider_account.roles.iter().any(|a| a.member == member && a.role == role) {
+            require!(
+                provider_account.roles.len() < MAX_PROVIDER_ROLES,
+                SubscriptionError::TooManyRoles
+            );
+            provider_account.roles.push(RoleAssignment { member, role });
+        }
+
+        emit!(ProviderRoleAdded {
+            provider: provider_account.authority,
+            member,
+            role,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a delegated role (Provider function)
+    pub fn remove_provider_role(
+        ctx: Context<UpdateProvider>,
+        member: Pubkey,
+        role: ProviderRole,
+    ) -> Result<()> {
+        let provider_account = &mut ctx.accounts.provider_account;
+
+        let index = provider_account.roles
+            .iter()
+            .position(|a| a.member == member && a.role == role)
+            .ok_or(SubscriptionError::RoleNotFound)?;
+        provider_account.roles.remove(index);
+
+        emit!(ProviderRoleRemoved {
+            provider: provider_account.authority,
+            member,
+            role,
+        });
+
+        Ok(())
+    }
+
     /// Create a subscription plan (Provider function)
     pub fn create_subscription_plan(
         ctx: Context<CreateSubscriptionPlan>,
@@ -58,6 +399,10 @@ pub mod subs3 {
 
         let plan = &mut ctx.accounts.subscription_plan;
         let manager = &mut ctx.accounts.subscription_manager;
+
+        if manager.mint_allowlist_enabled {
+            require!(ctx.accounts.allowed_mint.is_some(), SubscriptionError::MintNotAllowed);
+        }
         
         plan.provider = ctx.accounts.provider.key();
         plan.plan_id = plan_id;
@@ -72,8 +417,44 @@ pub mod subs3 {
         plan.is_active = true;
         plan.created_at = Clock::get()?.unix_timestamp;
         plan.bump = ctx.bumps.subscription_plan;
+        plan.prepay_bundles = Vec::new();
+        plan.metered = None;
+        plan.pricing_schedule = None;
+        plan.current_seats = 0;
+        plan.sunset_at = None;
+        plan.owner = plan.provider;
+        plan.pending_owner = None;
+        plan.max_members = 0;
+        plan.membership_mint = None;
+        plan.crank_reward = None;
 
-        manager.total_providers = manager.total_providers
+        // The provider account is created with the provider's first plan unless
+        // it was registered up front with `register_provider`
+        let provider_account = &mut ctx.accounts.provider_account;
+        if provider_account.authority == Pubkey::default() {
+            provider_account.initialize(
+                plan.provider,
+                String::new(),
+                String::new(),
+                plan.created_at,
+                ctx.bumps.provider_account,
+            );
+
+            manager.total_providers = manager.total_providers
+                .checked_add(1)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+            emit!(ProviderRegistered {
+                provider: provider_account.authority,
+                name: provider_account.name.clone(),
+                metadata_uri: provider_account.metadata_uri.clone(),
+            });
+        }
+        provider_account.plan_count = provider_account.plan_count
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        manager.total_plans = manager.total_plans
             .checked_add(1)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
 
@@ -89,7 +470,8 @@ pub mod subs3 {
     }
 
     /// Subscribe to a plan (Subscriber function)
-    pub fn subscribe(ctx: Context<Subscribe>) -> Result<()> {
+    /// `quantity` is the number of seats; single-user plans subscribe with 1
+    pub fn subscribe(ctx: Context<Subscribe>, quantity: u32) -> Result<()> {
         let plan = &mut ctx.accounts.subscription_plan;
         let subscription = &mut ctx.accounts.subscription;
         let manager = &mut ctx.accounts.subscription_manager;
@@ -102,12 +484,18 @@ pub mod subs3 {
         
         // Prevent providers from subscribing to their own plans
         require!(
-            ctx.accounts.subscriber.key() != plan.provider,
+            ctx.accounts.subscriber.key() != plan.owner,
             SubscriptionError::Unauthorized
         );
         
+        require!(quantity > 0, SubscriptionError::InvalidQuantity);
+
+        // Capacity is measured in seats, not subscriber accounts
+        let new_seats = plan.current_seats
+            .checked_add(quantity)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
         if let Some(max_subs) = plan.max_subscribers {
-            require!(plan.current_subscribers < max_subs, SubscriptionError::PlanAtCapacity);
+            require!(new_seats <= max_subs, SubscriptionError::PlanAtCapacity);
         }
 
         let clock = Clock::get()?;
@@ -118,20 +506,31 @@ pub mod subs3 {
             SubscriptionError::ArithmeticOverflow
         );
         
-        subscription.subscriber = ctx.accounts.subscriber.key();
-        subscription.subscription_plan = plan.key();
-        subscription.start_time = clock.unix_timestamp;
-        subscription.next_payment_due = clock.unix_timestamp + plan.period_duration_seconds;
-        subscription.is_active = true;
-        subscription.is_paused = false;
-        subscription.total_payments_made = 0;
-        subscription.total_amount_paid = 0;
-        subscription.payment_nonce = 0;
-        subscription.bump = ctx.bumps.subscription;
+        subscription.initialize(
+            ctx.accounts.subscriber.key(),
+            plan.key(),
+            clock.unix_timestamp,
+            clock.unix_timestamp + plan.period_duration_seconds,
+            quantity,
+            ctx.bumps.subscription,
+        );
+
+        // Plans with a membership token mint one to the new subscriber
+        issue_membership_token_if_enabled(
+            plan,
+            ctx.accounts.membership_mint.as_ref(),
+            ctx.accounts.membership_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+        )?;
 
+        plan.current_seats = new_seats;
         plan.current_subscribers = plan.current_subscribers
             .checked_add(1)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.active_subscribers = provider_account.active_subscribers
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
         manager.total_subscriptions = manager.total_subscriptions
             .checked_add(1)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
@@ -141,46 +540,51 @@ pub mod subs3 {
             subscription_plan: subscription.subscription_plan,
             start_time: subscription.start_time,
             next_payment_due: subscription.next_payment_due,
+            quantity: subscription.quantity,
         });
 
         Ok(())
     }
 
-    /// Process payment for a subscription (Can be called by anyone - automated)
-    /// 
-    /// Payment Flow Explanation:
-    /// 1. Subscriber has tokens in their token account (e.g., USDC)
-    /// 2. Provider has a dedicated vault (PDA) for each subscription plan
-    /// 3. When payment is due, tokens are transferred from subscriber → provider vault
-    /// 4. Provider can later withdraw funds from their vault to their personal account
-    /// 
-    /// Security Features: 
-    /// - The vault is a PDA controlled by the program, not directly by provider
-    /// - Only the program can move funds from vault (via withdraw_funds instruction)
-    /// - Balance validation prevents insufficient fund scenarios
-    /// - Overflow protection on all arithmetic operations
-    /// - Payment nonce prevents replay attacks
-    /// - Grace period prevents timing manipulation attacks
-    /// - This prevents providers from stealing funds or draining subscriber accounts
-    pub fn process_payment(ctx: Context<ProcessPayment>) -> Result<()> {
+    /// Gift a number of prepaid periods of a plan to another wallet (Payer function)
+    /// The beneficiary's subscription is paid through the gifted periods and then stops,
+    /// unless the beneficiary opts in to paying for renewals with `set_renews_after_gift`.
+    /// With `anonymous`, the gifter is left out of the emitted event.
+    pub fn gift_subscription(
+        ctx: Context<GiftSubscription>,
+        periods: u16,
+        anonymous: bool,
+    ) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
         let subscription = &mut ctx.accounts.subscription;
-        let plan = &mut ctx.accounts.subscription_plan; // Make mutable to update revenue
+        let manager = &mut ctx.accounts.subscription_manager;
         let clock = Clock::get()?;
 
-        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
-        require!(!subscription.is_paused, SubscriptionError::SubscriptionPaused);
+        require!(periods > 0, SubscriptionError::InvalidGiftPeriods);
+
+        let new_seats = plan.current_seats
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        if let Some(max_subs) = plan.max_subscribers {
+            require!(new_seats <= max_subs, SubscriptionError::PlanAtCapacity);
+        }
+
+        let amount = plan.price_per_period
+            .checked_mul(periods as u64)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let paid_through = plan.period_duration_seconds
+            .checked_mul(periods as i64)
+            .and_then(|duration| clock.unix_timestamp.checked_add(duration))
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
         require!(
-            clock.unix_timestamp >= subscription.next_payment_due - PAYMENT_GRACE_PERIOD,
-            SubscriptionError::PaymentNotDue
+            ctx.accounts.payer_token_account.amount >= amount,
+            SubscriptionError::InsufficientFunds
         );
 
-        // Transfer tokens from subscriber to provider vault
-        // This is the core payment mechanism:
-        // subscriber_token_account (user's USDC) → provider_vault (plan's USDC vault)
         let transfer_instruction = Transfer {
-            from: ctx.accounts.subscriber_token_account.to_account_info(),
+            from: ctx.accounts.payer_token_account.to_account_info(),
             to: ctx.accounts.provider_vault.to_account_info(),
-            authority: ctx.accounts.subscriber.to_account_info(), // Subscriber must sign
+            authority: ctx.accounts.payer.to_account_info(),
         };
 
         token::transfer(
@@ -188,44 +592,1947 @@ pub mod subs3 {
                 ctx.accounts.token_program.to_account_info(),
                 transfer_instruction,
             ),
-            plan.price_per_period, // Amount to transfer (e.g., 10 USDC)
+            amount,
         )?;
 
-        // Update subscription state with overflow protection
-        subscription.next_payment_due = subscription.next_payment_due
-            .checked_add(plan.period_duration_seconds)
+        subscription.initialize(
+            ctx.accounts.beneficiary.key(),
+            plan.key(),
+            clock.unix_timestamp,
+            paid_through,
+            1,
+            ctx.bumps.subscription,
+        );
+        subscription.payer = ctx.accounts.payer.key();
+        subscription.paid_sponsor = Some(Sponsor::Gifter(ctx.accounts.payer.key()));
+        subscription.prepaid_through = Some(paid_through);
+        subscription.total_payments_made = 1;
+        subscription.total_amount_paid = amount;
+        subscription.payment_nonce = 1;
+        subscription.paid_amount = amount;
+
+        issue_membership_token_if_enabled(
+            plan,
+            ctx.accounts.membership_mint.as_ref(),
+            ctx.accounts.membership_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+        )?;
+
+        plan.current_seats = new_seats;
+        plan.current_subscribers = plan.current_subscribers
+            .checked_add(1)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
-        subscription.total_payments_made = subscription.total_payments_made
+        plan.total_revenue = plan.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.active_subscribers = provider_account.active_subscribers
             .checked_add(1)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
-        subscription.total_amount_paid = subscription.total_amount_paid
-            .checked_add(plan.price_per_period)
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(amount)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
-        subscription.payment_nonce = subscription.payment_nonce
+        manager.total_subscriptions = manager.total_subscriptions
             .checked_add(1)
             .ok_or(SubscriptionError::ArithmeticOverflow)?;
 
-        // Update plan revenue tracking with overflow protection
-        // This tracks total revenue generated by this specific plan
-        // Useful for analytics, provider dashboards, and plan performance metrics
-        plan.total_revenue = plan.total_revenue
-            .checked_add(plan.price_per_period)
-            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        emit!(SubscriptionGifted {
+            gifter: if anonymous { None } else { Some(ctx.accounts.payer.key()) },
+            beneficiary: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            periods,
+            amount,
+            paid_through,
+        });
 
-        emit!(PaymentProcessed {
+        Ok(())
+    }
+
+    /// Choose whether to take over payments once gifted periods run out (Subscriber function)
+    /// Only the beneficiary can commit their own wallet to renewals
+    pub fn set_renews_after_gift(ctx: Context<SetRenewsAfterGift>, renews_after_gift: bool) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        require!(subscription.is_gift(), SubscriptionError::NotAGift);
+        subscription.renews_after_gift = renews_after_gift;
+
+        emit!(GiftRenewalUpdated {
             subscriber: subscription.subscriber,
             subscription_plan: subscription.subscription_plan,
-            amount: plan.price_per_period,
-            payment_number: subscription.total_payments_made,
-            payment_nonce: subscription.payment_nonce,
-            next_payment_due: subscription.next_payment_due,
+            renews_after_gift,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a subscription (Subscriber function)
+    /// Stops future renewals and frees the subscription's seats on the plan
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &mut ctx.accounts.subscription_plan;
+        let clock = Clock::get()?;
+
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+
+        subscription.is_active = false;
+        subscription.cancelled_at = Some(clock.unix_timestamp);
+
+        plan.current_subscribers = plan.current_subscribers.saturating_sub(1);
+        plan.current_seats = plan.current_seats.saturating_sub(subscription.quantity);
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.active_subscribers = provider_account.active_subscribers.saturating_sub(1);
+
+        // Burn the membership token now if its accounts were passed, otherwise
+        // `burn_membership_token` can do it later
+        if let (Some(mint), Some(token_account), Some(token_program)) = (
+            ctx.accounts.membership_mint.as_ref(),
+            ctx.accounts.membership_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+        ) {
+            if token_account.amount > 0 {
+                revoke_membership_token(
+                    plan,
+                    mint.to_account_info(),
+                    token_account.to_account_info(),
+                    token_program.to_account_info(),
+                    token_account.amount,
+                )?;
+
+                emit!(MembershipTokenBurned {
+                    subscription_plan: plan.key(),
+                    holder: subscription.subscriber,
+                    mint: mint.key(),
+                    amount: token_account.amount,
+                });
+            }
+        }
+
+        emit!(SubscriptionCancelled {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            cancelled_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Restart a cancelled subscription (Subscriber function)
+    /// Subject to the same checks as `subscribe`, blocklist included. Unlike a new
+    /// subscription there is no free first period: the first period is charged right away,
+    /// so cancelling and reactivating never extends service for free. Payment history,
+    /// spending limits and lifetime stats carry over; add-ons have to be attached again.
+    pub fn reactivate(ctx: Context<Reactivate>, quantity: u32) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        let subscription = &mut ctx.accounts.subscription;
+        let clock = Clock::get()?;
+
+        require!(!subscription.is_active, SubscriptionError::SubscriptionStillActive);
+        require!(
+            ctx.accounts.subscriber.key() != plan.owner,
+            SubscriptionError::Unauthorized
+        );
+        require!(quantity > 0, SubscriptionError::InvalidQuantity);
+
+        let new_seats = plan.current_seats
+            .checked_add(quantity)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        if let Some(max_subs) = plan.max_subscribers {
+            require!(new_seats <= max_subs, SubscriptionError::PlanAtCapacity);
+        }
+
+        subscription.is_active = true;
+        subscription.is_paused = false;
+        subscription.paused_at = None;
+        subscription.cancelled_at = None;
+        subscription.next_payment_due = clock.unix_timestamp;
+        subscription.prepaid_through = None;
+        subscription.current_period_usage = 0;
+        subscription.quantity = quantity;
+        subscription.credit_balance = 0;
+        subscription.add_ons = Vec::new();
+        subscription.payer = subscription.subscriber;
+        subscription.renews_after_gift = false;
+        subscription.organization = None;
+        subscription.paid_amount = 0;
+        subscription.paid_from = clock.unix_timestamp;
+        subscription.paid_until = clock.unix_timestamp;
+        subscription.prepaid_amount = 0;
+
+        let breakdown = subscription.payment_breakdown(plan)?;
+        let amount = breakdown.amount;
+        require!(
+            ctx.accounts.subscriber_token_account.amount >= amount,
+            SubscriptionError::InsufficientFunds
+        );
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.subscriber_token_account.to_account_info(),
+            to: ctx.accounts.provider_vault.to_account_info(),
+            authority: ctx.accounts.subscriber.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+            ),
+            amount,
+        )?;
+
+        subscription.record_payment(plan, &breakdown, clock.unix_timestamp)?;
+        subscription.record_spending(amount, clock.unix_timestamp)?;
+
+        issue_membership_token_if_enabled(
+            plan,
+            ctx.accounts.membership_mint.as_ref(),
+            ctx.accounts.membership_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+        )?;
+
+        plan.current_seats = new_seats;
+        plan.current_subscribers = plan.current_subscribers
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        plan.total_revenue = plan.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.active_subscribers = provider_account.active_subscribers
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(SubscriptionReactivated {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            quantity,
+            amount,
+            next_payment_due: subscription.next_payment_due,
+        });
+
+        Ok(())
+    }
+
+    /// Move a subscription to another wallet (Subscriber and recipient function)
+    /// Both wallets sign. The recipient's subscription carries over status, billing
+    /// schedule, and lifetime stats, and the old account is closed. A membership token
+    /// moves too: the subscriber's is burned and the recipient is minted one.
+    /// A self-paid subscription is paid by the recipient from then on. An active gift
+    /// moves with it: the gifted periods stay paid, and once they run out the subscription
+    /// ends unless the recipient opts in to renewals with `set_renews_after_gift`.
+    pub fn transfer_subscription(ctx: Context<TransferSubscription>) -> Result<()> {
+        let previous = (*ctx.accounts.subscription).clone();
+        // Member links are tied to the subscription address, which changes on transfer
+        require!(previous.member_count == 0, SubscriptionError::SubscriptionHasMembers);
+        let new_subscription = &mut ctx.accounts.new_subscription;
+        let recipient = ctx.accounts.recipient.key();
+        let payer = if previous.is_gift() { previous.payer } else { recipient };
+
+        new_subscription.set_inner(Subscription {
+            subscriber: recipient,
+            payer,
+            // The previous holder's opt-in does not commit the recipient's wallet
+            renews_after_gift: false,
+            bump: ctx.bumps.new_subscription,
+            ..previous
+        });
+
+        // The membership token follows the subscription to the recipient
+        let plan = &ctx.accounts.subscription_plan;
+        revoke_membership_token_if_enabled(
+            plan,
+            ctx.accounts.membership_mint.as_ref(),
+            ctx.accounts.membership_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+        )?;
+        if new_subscription.is_active {
+            issue_membership_token_if_enabled(
+                plan,
+                ctx.accounts.membership_mint.as_ref(),
+                ctx.accounts.recipient_membership_token_account.as_ref(),
+                ctx.accounts.token_2022_program.as_ref(),
+            )?;
+        }
+
+        emit!(SubscriptionTransferred {
+            subscription_plan: new_subscription.subscription_plan,
+            from: ctx.accounts.subscriber.key(),
+            to: recipient,
+            subscription: new_subscription.key(),
+            transferred_at: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Process payment for a subscription (Can be called by anyone - automated)
+    /// 
+    /// Payment Flow Explanation:
+    /// 1. Subscriber has tokens in their token account (e.g., USDC)
+    /// 2. Provider has a dedicated vault (PDA) for each subscription plan
+    /// 3. When payment is due, tokens are transferred from subscriber → provider vault
+    /// 4. Provider can later withdraw funds from their vault to their personal account
+    /// 
+    /// Security Features: 
+    /// - The vault is a PDA controlled by the program, not directly by provider
+    /// - Only the program can move funds from vault (via withdraw_funds instruction)
+    /// - Balance validation prevents insufficient fund scenarios
+    /// - Overflow protection on all arithmetic operations
+    /// - Payment nonce prevents replay attacks
+    /// - Grace period prevents timing manipulation attacks
+    /// - This prevents providers from stealing funds or draining subscriber accounts
+    pub fn process_payment(ctx: Context<ProcessPayment>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &mut ctx.accounts.subscription_plan; // Make mutable to update revenue
+        let clock = Clock::get()?;
+
+        subscription.require_renewal_due(plan, clock.unix_timestamp)?;
+        // Organization-sponsored subscriptions are charged through process_organization_payment
+        require!(subscription.organization.is_none(), SubscriptionError::SubscriptionSponsored);
+        // Once gifted periods run out the subscriber either takes over or the gift ends
+        let is_gift = subscription.is_gift();
+        require!(!is_gift || subscription.renews_after_gift, SubscriptionError::GiftEnded);
+
+        let breakdown = subscription.payment_breakdown(plan)?;
+        let amount = breakdown.amount;
+
+        // Renewals over the subscriber's limits are skipped rather than failed so
+        // the event is kept; the subscription lapses unless the limits are raised
+        if let Some(limit) = subscription.exceeded_spending_limit(amount, clock.unix_timestamp) {
+            emit!(PaymentBlockedByLimit {
+                subscriber: subscription.subscriber,
+                subscription_plan: subscription.subscription_plan,
+                amount,
+                limit,
+                spent_in_window: subscription.spent_in_window(clock.unix_timestamp),
+            });
+            return Ok(());
+        }
+
+        require!(
+            ctx.accounts.subscriber_token_account.amount >= amount,
+            SubscriptionError::InsufficientFunds
+        );
+
+        // The subscriber takes over paying only once their first renewal goes through
+        if is_gift {
+            subscription.payer = subscription.subscriber;
+        }
+
+        // A keeper passing its token account gets the plan's crank reward out of the
+        // provider's share; the subscriber is charged the same either way
+        let manager = &ctx.accounts.subscription_manager;
+        let crank_reward = match (plan.crank_reward.as_ref(), ctx.accounts.cranker_token_account.as_ref()) {
+            (Some(reward), Some(_)) => crank_reward_amount(reward, amount, manager.max_crank_reward),
+            _ => 0,
+        };
+        let provider_amount = amount - crank_reward;
+
+        // Transfer tokens from subscriber to provider vault
+        // This is the core payment mechanism:
+        // subscriber_token_account (user's USDC) → provider_vault (plan's USDC vault)
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.subscriber_token_account.to_account_info(),
+            to: ctx.accounts.provider_vault.to_account_info(),
+            authority: ctx.accounts.subscriber.to_account_info(), // Subscriber must sign
+        };
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+            ),
+            provider_amount, // Amount to transfer (e.g., 10 USDC plus usage, less any crank reward)
+        )?;
+
+        if let (true, Some(cranker_token_account)) = (crank_reward > 0, ctx.accounts.cranker_token_account.as_ref()) {
+            let transfer_instruction = Transfer {
+                from: ctx.accounts.subscriber_token_account.to_account_info(),
+                to: cranker_token_account.to_account_info(),
+                authority: ctx.accounts.subscriber.to_account_info(),
+            };
+
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    transfer_instruction,
+                ),
+                crank_reward,
+            )?;
+
+            emit!(CrankRewardPaid {
+                subscription_plan: subscription.subscription_plan,
+                subscriber: subscription.subscriber,
+                cranker: cranker_token_account.owner,
+                amount: crank_reward,
+            });
+        }
+
+        let payment_nonce = subscription.payment_nonce;
+        let period_start = subscription.next_payment_due;
+
+        // Update subscription state with overflow protection
+        subscription.record_payment(plan, &breakdown, clock.unix_timestamp)?;
+        subscription.record_spending(amount, clock.unix_timestamp)?;
+
+        // Keep an on-chain receipt if the caller asked for one
+        if let (Some(receipt), Some(bump)) = (ctx.accounts.receipt.as_mut(), ctx.bumps.receipt) {
+            receipt.set_inner(Receipt {
+                subscription: subscription.key(),
+                subscriber: subscription.subscriber,
+                subscription_plan: subscription.subscription_plan,
+                payment_nonce,
+                mint: plan.payment_token,
+                amount,
+                base_amount: breakdown.base_amount,
+                usage_units: breakdown.usage_units,
+                usage_amount: breakdown.usage_amount,
+                add_on_amount: breakdown.add_on_amount,
+                credit_applied: breakdown.credit_applied,
+                period_start,
+                period_end: subscription.next_payment_due,
+                paid_at: clock.unix_timestamp,
+                bump,
+            });
+
+            emit!(ReceiptCreated {
+                receipt: receipt.key(),
+                subscription: subscription.key(),
+                payment_nonce,
+                amount,
+            });
+        }
+
+        // Update plan revenue tracking with overflow protection
+        // This tracks total revenue generated by this specific plan
+        // Useful for analytics, provider dashboards, and plan performance metrics
+        plan.total_revenue = plan.total_revenue
+            .checked_add(provider_amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(provider_amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(PaymentProcessed {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            amount,
+            base_amount: breakdown.base_amount,
+            usage_units: breakdown.usage_units,
+            usage_amount: breakdown.usage_amount,
+            credit_applied: breakdown.credit_applied,
+            add_on_amount: breakdown.add_on_amount,
+            add_ons: subscription.add_ons.clone(),
+            payment_number: subscription.total_payments_made,
+            payment_nonce: subscription.payment_nonce,
+            next_payment_due: subscription.next_payment_due,
+        });
+
+        Ok(())
+    }
+
+    /// Process due renewals for many subscriptions of one plan (Can be called by anyone - automated)
+    ///
+    /// `remaining_accounts` holds (subscription, subscriber token account, payment delegate)
+    /// triples, the first two writable. Keepers cannot collect subscriber signatures, so each
+    /// subscriber approves the subscription's payment delegate PDA on the token account and
+    /// the PDA signs the transfer. The PDA is seeded by the subscription, so an approval only
+    /// ever pays renewals of that one subscription, within its spending limits. A token
+    /// account has a single delegate, so each subscription renewed this way needs its own.
+    /// Gifts are only charged once the beneficiary has opted in with `set_renews_after_gift`.
+    /// Items that cannot be charged are skipped with a `BatchPaymentResult` event instead
+    /// of failing the batch; malformed triples still fail it.
+    pub fn process_payments_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessPaymentsBatch<'info>>,
+    ) -> Result<()> {
+        let remaining_accounts = ctx.remaining_accounts;
+        let items = remaining_accounts.chunks_exact(3);
+        require!(
+            !remaining_accounts.is_empty() && items.remainder().is_empty(),
+            SubscriptionError::InvalidBatch
+        );
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        let plan_key = plan.key();
+        let manager = &ctx.accounts.subscription_manager;
+        let now = Clock::get()?.unix_timestamp;
+        let mut paid = 0u16;
+        let mut total_amount = 0u64;
+        let mut total_crank_reward = 0u64;
+
+        for (index, item) in items.enumerate() {
+            let compute_start = sol_remaining_compute_units();
+            let mut subscription = Account::<Subscription>::try_from(&item[0])?;
+            let subscriber_token_account = Account::<TokenAccount>::try_from(&item[1])?;
+            let payment_delegate = &item[2];
+            require_keys_eq!(subscription.subscription_plan, plan_key, SubscriptionError::InvalidBatch);
+            let subscription_key = subscription.key();
+            let subscriber_key = subscription.subscriber;
+            let (payment_delegate_key, payment_delegate_bump) = Pubkey::find_program_address(
+                &[PAYMENT_DELEGATE_SEED, subscription_key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(payment_delegate.key(), payment_delegate_key, SubscriptionError::InvalidBatch);
+            require_keys_eq!(
+                subscriber_token_account.owner,
+                subscription.subscriber,
+                SubscriptionError::Unauthorized
+            );
+            require_keys_eq!(
+                subscriber_token_account.mint,
+                plan.payment_token,
+                SubscriptionError::InvalidTokenMint
+            );
+
+            let mut amount = 0;
+            let status = if subscription.require_renewal_due(plan, now).is_err() {
+                BatchPaymentStatus::NotDue
+            } else if subscription.organization.is_some()
+                || (subscription.is_gift() && !subscription.renews_after_gift)
+            {
+                BatchPaymentStatus::Ineligible
+            } else {
+                let breakdown = subscription.payment_breakdown(plan)?;
+                amount = breakdown.amount;
+
+                if let Some(limit) = subscription.exceeded_spending_limit(amount, now) {
+                    emit!(PaymentBlockedByLimit {
+                        subscriber: subscription.subscriber,
+                        subscription_plan: plan_key,
+                        amount,
+                        limit,
+                        spent_in_window: subscription.spent_in_window(now),
+                    });
+                    BatchPaymentStatus::SpendingLimit
+                } else if subscriber_token_account.delegate != COption::Some(payment_delegate_key)
+                    || subscriber_token_account.delegated_amount < amount
+                {
+                    BatchPaymentStatus::NotDelegated
+                } else if subscriber_token_account.amount < amount {
+                    BatchPaymentStatus::InsufficientFunds
+                } else {
+                    let payment_delegate_seeds = &[
+                        PAYMENT_DELEGATE_SEED,
+                        subscription_key.as_ref(),
+                        &[payment_delegate_bump],
+                    ];
+
+                    let crank_reward = match (plan.crank_reward.as_ref(), ctx.accounts.cranker_token_account.as_ref()) {
+                        (Some(reward), Some(_)) => crank_reward_amount(reward, amount, manager.max_crank_reward),
+                        _ => 0,
+                    };
+
+                    let transfer_instruction = Transfer {
+                        from: item[1].clone(),
+                        to: ctx.accounts.provider_vault.to_account_info(),
+                        authority: payment_delegate.clone(),
+                    };
+
+                    token::transfer(
+                        CpiContext::new_with_signer(
+                            ctx.accounts.token_program.to_account_info(),
+                            transfer_instruction,
+                            &[payment_delegate_seeds],
+                        ),
+                        amount - crank_reward,
+                    )?;
+
+                    if let (true, Some(cranker_token_account)) = (crank_reward > 0, ctx.accounts.cranker_token_account.as_ref()) {
+                        let transfer_instruction = Transfer {
+                            from: item[1].clone(),
+                            to: cranker_token_account.to_account_info(),
+                            authority: payment_delegate.clone(),
+                        };
+
+                        token::transfer(
+                            CpiContext::new_with_signer(
+                                ctx.accounts.token_program.to_account_info(),
+                                transfer_instruction,
+                                &[payment_delegate_seeds],
+                            ),
+                            crank_reward,
+                        )?;
+
+                        total_crank_reward = total_crank_reward
+                            .checked_add(crank_reward)
+                            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+                        emit!(CrankRewardPaid {
+                            subscription_plan: plan_key,
+                            subscriber: subscriber_key,
+                            cranker: cranker_token_account.owner,
+                            amount: crank_reward,
+                        });
+                    }
+
+                    // Once gifted periods run out the subscriber takes over payments
+                    if subscription.is_gift() {
+                        subscription.payer = subscription.subscriber;
+                    }
+                    subscription.record_payment(plan, &breakdown, now)?;
+                    subscription.record_spending(amount, now)?;
+                    subscription.exit(&crate::ID)?;
+
+                    total_amount = total_amount
+                        .checked_add(amount)
+                        .ok_or(SubscriptionError::ArithmeticOverflow)?;
+                    paid += 1;
+
+                    emit!(PaymentProcessed {
+                        subscriber: subscription.subscriber,
+                        subscription_plan: plan_key,
+                        amount,
+                        base_amount: breakdown.base_amount,
+                        usage_units: breakdown.usage_units,
+                        usage_amount: breakdown.usage_amount,
+                        credit_applied: breakdown.credit_applied,
+                        add_on_amount: breakdown.add_on_amount,
+                        add_ons: subscription.add_ons.clone(),
+                        payment_number: subscription.total_payments_made,
+                        payment_nonce: subscription.payment_nonce,
+                        next_payment_due: subscription.next_payment_due,
+                    });
+                    BatchPaymentStatus::Paid
+                }
+            };
+
+            let compute_units = compute_start.saturating_sub(sol_remaining_compute_units());
+            emit!(BatchPaymentResult {
+                subscription: subscription.key(),
+                subscriber: subscription.subscriber,
+                index: index as u16,
+                status,
+                amount,
+                compute_units,
+            });
+        }
+
+        // Crank rewards come out of the provider's share
+        let provider_amount = total_amount - total_crank_reward;
+        plan.total_revenue = plan.total_revenue
+            .checked_add(provider_amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(provider_amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(BatchPaymentsProcessed {
+            subscription_plan: plan_key,
+            items: (remaining_accounts.len() / 3) as u16,
+            paid,
+            total_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Set limits on what renewals may charge (Subscriber function)
+    /// Passing all limits as `None` removes them
+    pub fn set_spending_limits(ctx: Context<SetSpendingLimits>, spending_limits: SpendingLimits) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.spending_limits = spending_limits;
+
+        emit!(SpendingLimitsUpdated {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            max_per_charge: spending_limits.max_per_charge,
+            max_per_window: spending_limits.max_per_window,
+            authorized_until: spending_limits.authorized_until,
+        });
+
+        Ok(())
+    }
+
+    /// Close a payment receipt and reclaim its rent (Subscriber function)
+    pub fn close_receipt(ctx: Context<CloseReceipt>) -> Result<()> {
+        let receipt = &ctx.accounts.receipt;
+
+        emit!(ReceiptClosed {
+            receipt: receipt.key(),
+            subscription: receipt.subscription,
+            payment_nonce: receipt.payment_nonce,
+        });
+
+        Ok(())
+    }
+
+    /// Grow a subscription created by an older program version to the current account size (Can be called by anyone)
+    /// New fields start zeroed, e.g. an empty payment history, except the seat count and
+    /// payer, which default to one seat paid by the subscriber. The payer covers the extra rent.
+    pub fn migrate_subscription(ctx: Context<MigrateSubscription>) -> Result<()> {
+        let subscription_info = ctx.accounts.subscription.to_account_info();
+        require!(
+            subscription_info.try_borrow_data()?.starts_with(Subscription::DISCRIMINATOR),
+            SubscriptionError::InvalidSubscriptionAccount
+        );
+
+        let old_len = subscription_info.data_len();
+        let new_len = 8 + Subscription::INIT_SPACE;
+        require!(old_len < new_len, SubscriptionError::SubscriptionUpToDate);
+
+        grow_account(
+            &subscription_info,
+            new_len,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let mut subscription = Subscription::try_deserialize(&mut &subscription_info.try_borrow_data()?[..])?;
+        subscription.apply_migration_defaults();
+        subscription.try_serialize(&mut &mut subscription_info.try_borrow_mut_data()?[..])?;
+
+        emit!(SubscriptionMigrated {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            old_len: old_len as u32,
+            new_len: new_len as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Grow the manager created by an older program version to the current account size (Can be called by anyone)
+    /// Must run before any plan is migrated. Plans move from `total_providers` to `total_plans`,
+    /// providers are recounted by `migrate_subscription_plan`, and exits stay open while paused.
+    pub fn migrate_manager(ctx: Context<MigrateManager>) -> Result<()> {
+        let manager_info = ctx.accounts.subscription_manager.to_account_info();
+        require!(
+            manager_info.try_borrow_data()?.starts_with(SubscriptionManager::DISCRIMINATOR),
+            SubscriptionError::InvalidMigrationAccount
+        );
+
+        let old_len = manager_info.data_len();
+        let new_len = 8 + SubscriptionManager::INIT_SPACE;
+        require!(old_len < new_len, SubscriptionError::AccountUpToDate);
+
+        grow_account(
+            &manager_info,
+            new_len,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let mut manager = SubscriptionManager::try_deserialize(&mut &manager_info.try_borrow_data()?[..])?;
+        manager.apply_migration_defaults();
+        manager.try_serialize(&mut &mut manager_info.try_borrow_mut_data()?[..])?;
+
+        emit!(ManagerMigrated {
+            old_len: old_len as u32,
+            new_len: new_len as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Grow a plan created by an older program version to the current account size (Can be called by anyone)
+    /// The provider owns the plan and each subscription holds one seat. The provider account
+    /// is created if the provider has none yet, and the plan's subscribers and revenue are
+    /// added to it. The payer covers the extra rent.
+    pub fn migrate_subscription_plan(ctx: Context<MigrateSubscriptionPlan>) -> Result<()> {
+        let plan_info = ctx.accounts.subscription_plan.to_account_info();
+        require!(
+            plan_info.try_borrow_data()?.starts_with(SubscriptionPlan::DISCRIMINATOR),
+            SubscriptionError::InvalidMigrationAccount
+        );
+
+        let old_len = plan_info.data_len();
+        let new_len = 8 + SubscriptionPlan::INIT_SPACE;
+        require!(old_len < new_len, SubscriptionError::AccountUpToDate);
+
+        grow_account(
+            &plan_info,
+            new_len,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let mut plan = SubscriptionPlan::try_deserialize(&mut &plan_info.try_borrow_data()?[..])?;
+        require_keys_eq!(plan.provider, ctx.accounts.provider.key(), SubscriptionError::Unauthorized);
+        plan.apply_migration_defaults();
+        plan.try_serialize(&mut &mut plan_info.try_borrow_mut_data()?[..])?;
+
+        let manager = &mut ctx.accounts.subscription_manager;
+        let provider_account = &mut ctx.accounts.provider_account;
+        if provider_account.authority == Pubkey::default() {
+            provider_account.initialize(
+                plan.provider,
+                String::new(),
+                String::new(),
+                plan.created_at,
+                ctx.bumps.provider_account,
+            );
+
+            manager.total_providers = manager.total_providers
+                .checked_add(1)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+            emit!(ProviderRegistered {
+                provider: provider_account.authority,
+                name: provider_account.name.clone(),
+                metadata_uri: provider_account.metadata_uri.clone(),
+            });
+        }
+        provider_account.plan_count = provider_account.plan_count
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        provider_account.active_subscribers = provider_account.active_subscribers
+            .checked_add(plan.current_subscribers)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(plan.total_revenue)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(SubscriptionPlanMigrated {
+            provider: plan.provider,
+            subscription_plan: plan_info.key(),
+            old_len: old_len as u32,
+            new_len: new_len as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Update a plan's display name and description (Provider or operator function)
+    pub fn update_plan_details(
+        ctx: Context<UpdatePlanDetails>,
+        name: String,
+        description: String,
+    ) -> Result<()> {
+        require!(name.len() <= MAX_NAME_LENGTH, SubscriptionError::NameTooLong);
+        require!(description.len() <= MAX_DESCRIPTION_LENGTH, SubscriptionError::DescriptionTooLong);
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        plan.name = name;
+        plan.description = description;
+
+        emit!(PlanDetailsUpdated {
+            subscription_plan: plan.key(),
+            updated_by: ctx.accounts.provider.key(),
+            name: plan.name.clone(),
+            description: plan.description.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Replace the prepay bundles offered by a plan (Provider function)
+    pub fn set_prepay_bundles(
+        ctx: Context<UpdateSubscriptionPlan>,
+        bundles: Vec<PrepayBundle>,
+    ) -> Result<()> {
+        require!(bundles.len() <= MAX_PREPAY_BUNDLES, SubscriptionError::TooManyPrepayBundles);
+
+        let plan = &mut ctx.accounts.subscription_plan;
+        for bundle in bundles.iter() {
+            require!(bundle.periods > 1, SubscriptionError::InvalidPrepayBundle);
+            require!(bundle.price > 0, SubscriptionError::InvalidPrice);
+            // Bundles must fit within the supported time range
+            let duration = plan.period_duration_seconds
+                .checked_mul(bundle.periods as i64)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            require!(
+                Clock::get()?.unix_timestamp.checked_add(duration).is_some(),
+                SubscriptionError::ArithmeticOverflow
+            );
+        }
+
+        plan.prepay_bundles = bundles;
+
+        emit!(PrepayBundlesUpdated {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            bundle_count: plan.prepay_bundles.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Configure or remove the metered usage component of a plan (Provider function)
+    /// `unit_price` may be zero once a pricing schedule is attached, since the schedule prices usage
+    pub fn set_metered_component(
+        ctx: Context<UpdateSubscriptionPlan>,
+        metered: Option<MeteredComponent>,
+    ) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        if let Some(component) = metered {
+            require!(
+                component.is_priced(plan.pricing_schedule.as_ref()),
+                SubscriptionError::InvalidPrice
+            );
+        }
+
+        plan.metered = metered;
+
+        emit!(MeteredComponentUpdated {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            unit_price: metered.map_or(0, |m| m.unit_price),
+            included_units: metered.map_or(0, |m| m.included_units),
+            overage_cap: metered.and_then(|m| m.overage_cap),
+        });
+
+        Ok(())
+    }
+
+    /// Configure or remove a tiered pricing schedule for metered usage (Provider function)
+    /// When set, usage beyond the included units is priced by the schedule instead of
+    /// the metered component's flat unit price
+    pub fn set_pricing_schedule(
+        ctx: Context<UpdateSubscriptionPlan>,
+        pricing_schedule: Option<PricingSchedule>,
+    ) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+
+        if let Some(schedule) = pricing_schedule.as_ref() {
+            require!(plan.metered.is_some(), SubscriptionError::PlanNotMetered);
+            require!(
+                is_valid_schedule(&schedule.tiers),
+                SubscriptionError::InvalidPricingSchedule
+            );
+        }
+        // Removing the schedule must leave usage with a price
+        if let Some(component) = plan.metered {
+            require!(
+                component.is_priced(pricing_schedule.as_ref()),
+                SubscriptionError::InvalidPrice
+            );
+        }
+
+        plan.pricing_schedule = pricing_schedule;
+
+        emit!(PricingScheduleUpdated {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            model: plan.pricing_schedule.as_ref().map(|s| s.model),
+            tier_count: plan.pricing_schedule.as_ref().map_or(0, |s| s.tiers.len() as u8),
+        });
+
+        Ok(())
+    }
+
+    /// Report usage for the current billing period (Provider or operator function)
+    /// Units accumulate on the subscription and are billed by the next `process_payment`
+    pub fn report_usage(ctx: Context<ReportUsage>, units: u64) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &ctx.accounts.subscription_plan;
+
+        require!(plan.metered.is_some(), SubscriptionError::PlanNotMetered);
+        require!(units > 0, SubscriptionError::InvalidUsage);
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+
+        subscription.current_period_usage = subscription.current_period_usage
+            .checked_add(units)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(UsageReported {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            units,
+            current_period_usage: subscription.current_period_usage,
+            next_payment_due: subscription.next_payment_due,
+        });
+
+        Ok(())
+    }
+
+    /// Prepay several periods at once using one of the plan's bundles (Subscriber function)
+    /// Charges the bundle price per seat, plus attached add-ons for every period, up front
+    /// and moves `next_payment_due` forward by the purchased number of periods. Cranks skip
+    /// the subscription until `prepaid_through`.
+    pub fn prepay(ctx: Context<Prepay>, bundle_index: u8) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &mut ctx.accounts.subscription_plan;
+        let clock = Clock::get()?;
+
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+        require!(!subscription.is_paused, SubscriptionError::SubscriptionPaused);
+
+        let bundle = *plan.prepay_bundles
+            .get(bundle_index as usize)
+            .ok_or(SubscriptionError::InvalidPrepayBundle)?;
+        // Bundle prices are per seat; add-ons are not discounted
+        let add_on_amount = subscription.add_on_total()?
+            .checked_mul(bundle.periods as u64)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let amount = bundle.price
+            .checked_mul(subscription.quantity as u64)
+            .and_then(|seats| seats.checked_add(add_on_amount))
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        require!(
+            ctx.accounts.subscriber_token_account.amount >= amount,
+            SubscriptionError::InsufficientFunds
+        );
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.subscriber_token_account.to_account_info(),
+            to: ctx.accounts.provider_vault.to_account_info(),
+            authority: ctx.accounts.subscriber.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+            ),
+            amount,
+        )?;
+
+        subscription.add_prepaid_value(amount, clock.unix_timestamp)?;
+        let duration = plan.period_duration_seconds
+            .checked_mul(bundle.periods as i64)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        subscription.next_payment_due = subscription.next_payment_due
+            .checked_add(duration)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        // Prepaid time cannot run past a scheduled sunset
+        if let Some(sunset_at) = plan.sunset_at {
+            require!(subscription.next_payment_due <= sunset_at, SubscriptionError::PlanSunset);
+        }
+        subscription.prepaid_through = Some(subscription.next_payment_due);
+        subscription.total_payments_made = subscription.total_payments_made
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        subscription.total_amount_paid = subscription.total_amount_paid
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        subscription.payment_nonce = subscription.payment_nonce
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        plan.total_revenue = plan.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(SubscriptionPrepaid {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            periods: bundle.periods,
+            amount,
+            payment_nonce: subscription.payment_nonce,
+            prepaid_through: subscription.next_payment_due,
+        });
+
+        Ok(())
+    }
+
+    /// Change the number of seats on a subscription (Subscriber function)
+    /// Increases are charged immediately, prorated to the time left until the next payment.
+    /// Decreases add a prorated credit, up to what was paid for the time left, that is
+    /// applied to upcoming payments.
+    pub fn update_quantity(ctx: Context<UpdateQuantity>, new_quantity: u32) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &mut ctx.accounts.subscription_plan;
+        let clock = Clock::get()?;
+
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+        require!(!subscription.is_paused, SubscriptionError::SubscriptionPaused);
+        require!(
+            new_quantity > 0 && new_quantity != subscription.quantity,
+            SubscriptionError::InvalidQuantity
+        );
+
+        let old_quantity = subscription.quantity;
+        let seat_delta = new_quantity.abs_diff(old_quantity);
+        let remaining_seconds = subscription.next_payment_due - clock.unix_timestamp;
+        let delta_price = plan.price_per_period
+            .checked_mul(seat_delta as u64)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let prorated_amount = prorate(delta_price, remaining_seconds, plan.period_duration_seconds)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        let mut prorated_charge = 0;
+        let mut credit_added = 0;
+        if new_quantity > old_quantity {
+            // Renewals of a sponsored subscription are billed to the organization
+            require!(subscription.organization.is_none(), SubscriptionError::SubscriptionSponsored);
+            plan.current_seats = plan.current_seats
+                .checked_add(seat_delta)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            if let Some(max_subs) = plan.max_subscribers {
+                require!(plan.current_seats <= max_subs, SubscriptionError::PlanAtCapacity);
+            }
+
+            if prorated_amount > 0 {
+                require!(
+                    ctx.accounts.subscriber_token_account.amount >= prorated_amount,
+                    SubscriptionError::InsufficientFunds
+                );
+
+                let transfer_instruction = Transfer {
+                    from: ctx.accounts.subscriber_token_account.to_account_info(),
+                    to: ctx.accounts.provider_vault.to_account_info(),
+                    authority: ctx.accounts.subscriber.to_account_info(),
+                };
+
+                token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        transfer_instruction,
+                    ),
+                    prorated_amount,
+                )?;
+
+                subscription.total_amount_paid = subscription.total_amount_paid
+                    .checked_add(prorated_amount)
+                    .ok_or(SubscriptionError::ArithmeticOverflow)?;
+                plan.total_revenue = plan.total_revenue
+                    .checked_add(prorated_amount)
+                    .ok_or(SubscriptionError::ArithmeticOverflow)?;
+                let provider_account = &mut ctx.accounts.provider_account;
+                provider_account.total_revenue = provider_account.total_revenue
+                    .checked_add(prorated_amount)
+                    .ok_or(SubscriptionError::ArithmeticOverflow)?;
+                subscription.add_paid_value(prorated_amount, clock.unix_timestamp)?;
+            }
+            prorated_charge = prorated_amount;
+        } else {
+            plan.current_seats = plan.current_seats.saturating_sub(seat_delta);
+            // Credit is only given for time that was actually paid for
+            let unused_value = subscription
+                .unused_value(clock.unix_timestamp)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            credit_added = prorated_amount
+                .min(unused_value.saturating_sub(subscription.credit_balance));
+            subscription.credit_balance = subscription.credit_balance
+                .checked_add(credit_added)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        }
+
+        subscription.quantity = new_quantity;
+
+        emit!(QuantityUpdated {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            old_quantity,
+            new_quantity,
+            prorated_charge,
+            credit_added,
+        });
+
+        Ok(())
+    }
+
+    /// Create an optional add-on for a plan (Provider function)
+    pub fn create_add_on(
+        ctx: Context<CreateAddOn>,
+        add_on_id: String,
+        name: String,
+        price_per_period: u64,
+    ) -> Result<()> {
+        require!(add_on_id.len() <= MAX_PLAN_ID_LENGTH, SubscriptionError::PlanIdTooLong);
+        require!(name.len() <= MAX_NAME_LENGTH, SubscriptionError::NameTooLong);
+        require!(price_per_period > 0, SubscriptionError::InvalidPrice);
+
+        let add_on = &mut ctx.accounts.add_on;
+        add_on.subscription_plan = ctx.accounts.subscription_plan.key();
+        add_on.add_on_id = add_on_id;
+        add_on.name = name;
+        add_on.price_per_period = price_per_period;
+        add_on.is_active = true;
+        add_on.created_at = Clock::get()?.unix_timestamp;
+        add_on.bump = ctx.bumps.add_on;
+
+        emit!(AddOnCreated {
+            provider: ctx.accounts.provider.key(),
+            subscription_plan: add_on.subscription_plan,
+            add_on: add_on.key(),
+            add_on_id: add_on.add_on_id.clone(),
+            price_per_period: add_on.price_per_period,
+        });
+
+        Ok(())
+    }
+
+    /// Attach an add-on to a subscription (Subscriber function)
+    /// The add-on is billed with every following `process_payment`. Prepaid and gifted
+    /// time was paid without it, so add-ons can only be attached once that time runs out.
+    pub fn attach_add_on(ctx: Context<AttachAddOn>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let add_on = &ctx.accounts.add_on;
+
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+        require!(subscription.organization.is_none(), SubscriptionError::SubscriptionSponsored);
+        if let Some(prepaid_through) = subscription.prepaid_through {
+            require!(
+                Clock::get()?.unix_timestamp >= prepaid_through,
+                SubscriptionError::SubscriptionPrepaid
+            );
+        }
+        require!(
+            !subscription.add_ons.iter().any(|attached| attached.add_on == add_on.key()),
+            SubscriptionError::AddOnAlreadyAttached
+        );
+        require!(
+            subscription.add_ons.len() < MAX_ADD_ONS_PER_SUBSCRIPTION,
+            SubscriptionError::TooManyAddOns
+        );
+
+        subscription.add_ons.push(AttachedAddOn {
+            add_on: add_on.key(),
+            price_per_period: add_on.price_per_period,
+        });
+
+        emit!(AddOnAttached {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            add_on: add_on.key(),
+            price_per_period: add_on.price_per_period,
+        });
+
+        Ok(())
+    }
+
+    /// Detach an add-on from a subscription (Subscriber function)
+    pub fn detach_add_on(ctx: Context<DetachAddOn>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let add_on_key = ctx.accounts.add_on.key();
+
+        let index = subscription.add_ons
+            .iter()
+            .position(|attached| attached.add_on == add_on_key)
+            .ok_or(SubscriptionError::AddOnNotAttached)?;
+        subscription.add_ons.remove(index);
+
+        emit!(AddOnDetached {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            add_on: add_on_key,
+        });
+
+        Ok(())
+    }
+
+    /// Check that a wallet currently has access to a plan (Read-only, meant for CPI)
+    /// Fails with `NotEntitled` unless the wallet is the subscriber, or an accepted member
+    /// passed in `subscription_member`, and the subscription is active, not paused and
+    /// paid through now (within the payment grace period)
+    pub fn verify_entitlement(ctx: Context<VerifyEntitlement>) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+        let wallet = ctx.accounts.wallet.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        let entitled = if wallet == subscription.subscriber {
+            subscription.is_entitled(now)
+        } else {
+            match ctx.accounts.subscription_member.as_ref() {
+                Some(subscription_member) => subscription_member.is_entitled(subscription.key(), subscription, now),
+                None => false,
+            }
+        };
+        require!(entitled, SubscriptionError::NotEntitled);
+
+        Ok(())
+    }
+
+    /// Issue a non-transferable Token-2022 membership token to the plan's subscribers (Provider function)
+    /// The plan PDA is the mint authority and permanent delegate, so the program can burn
+    /// tokens once a subscription ends. Tokens are minted by `subscribe`, `reactivate`,
+    /// `gift_subscription` and `transfer_subscription`, and burned by `transfer_subscription`
+    /// and `expire_sunset_subscription`; other lapses are burned with `burn_membership_token`
+    pub fn enable_membership_token(ctx: Context<EnableMembershipToken>) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        require!(plan.membership_mint.is_none(), SubscriptionError::MembershipTokenAlreadyEnabled);
+
+        create_membership_mint(
+            plan,
+            ctx.accounts.membership_mint.to_account_info(),
+            ctx.bumps.membership_mint,
+            ctx.accounts.provider.to_account_info(),
+            ctx.accounts.token_2022_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        plan.membership_mint = Some(ctx.accounts.membership_mint.key());
+
+        emit!(MembershipTokenEnabled {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            mint: ctx.accounts.membership_mint.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Mint a membership token to an entitled subscriber without one (Can be called by anyone)
+    /// Covers subscriptions that became entitled again, e.g. after a lapsed payment went through
+    pub fn mint_membership_token(ctx: Context<MintMembershipToken>) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+        let token_account = &ctx.accounts.membership_token_account;
+
+        require!(
+            subscription.is_entitled(Clock::get()?.unix_timestamp),
+            SubscriptionError::NotEntitled
+        );
+        require!(token_account.amount == 0, SubscriptionError::MembershipTokenHeld);
+
+        issue_membership_token(
+            &ctx.accounts.subscription_plan,
+            ctx.accounts.membership_mint.to_account_info(),
+            token_account.to_account_info(),
+            ctx.accounts.token_2022_program.to_account_info(),
+        )?;
+
+        emit!(MembershipTokenMinted {
+            subscription_plan: subscription.subscription_plan,
+            holder: subscription.subscriber,
+            mint: ctx.accounts.membership_mint.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Burn the membership token of a wallet whose subscription was cancelled, expired,
+    /// lapsed or transferred away (Can be called by anyone - automated)
+    pub fn burn_membership_token(ctx: Context<BurnMembershipToken>) -> Result<()> {
+        let token_account = &ctx.accounts.membership_token_account;
+        let subscription_info = &ctx.accounts.subscription;
+
+        let entitled = if subscription_info.data_is_empty() {
+            false
+        } else {
+            let data = subscription_info.try_borrow_data()?;
+            Subscription::try_deserialize(&mut &data[..])?
+                .is_entitled(Clock::get()?.unix_timestamp)
+        };
+        require!(!entitled, SubscriptionError::MembershipStillEntitled);
+
+        let amount = token_account.amount;
+        require!(amount > 0, SubscriptionError::NoMembershipToken);
+
+        revoke_membership_token(
+            &ctx.accounts.subscription_plan,
+            ctx.accounts.membership_mint.to_account_info(),
+            token_account.to_account_info(),
+            ctx.accounts.token_2022_program.to_account_info(),
+            amount,
+        )?;
+
+        emit!(MembershipTokenBurned {
+            subscription_plan: ctx.accounts.subscription_plan.key(),
+            holder: token_account.owner,
+            mint: ctx.accounts.membership_mint.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Configure or remove the reward paid to keepers processing renewals (Provider function)
+    /// The reward comes out of the provider's share of each renewal, never on top of it, and
+    /// goes to whoever cranks it, the subscriber included: it is a cost the provider opts into
+    /// per renewal, so who collects it does not change what the subscriber pays
+    pub fn set_crank_reward(ctx: Context<SetCrankReward>, crank_reward: Option<CrankReward>) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        let manager = &ctx.accounts.subscription_manager;
+
+        match crank_reward {
+            Some(CrankReward::Fixed { amount }) => require!(
+                amount >= manager.min_crank_reward && amount <= manager.max_crank_reward,
+                SubscriptionError::InvalidCrankReward
+            ),
+            Some(CrankReward::Bps { bps, cap }) => require!(
+                bps > 0
+                    && bps <= BPS_DENOMINATOR
+                    && cap >= manager.min_crank_reward
+                    && cap <= manager.max_crank_reward,
+                SubscriptionError::InvalidCrankReward
+            ),
+            None => {}
+        }
+
+        plan.crank_reward = crank_reward;
+
+        emit!(CrankRewardUpdated {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            crank_reward,
+        });
+
+        Ok(())
+    }
+
+    /// Set how many wallets each subscription can share access with (Provider function)
+    /// Lowering the limit keeps existing members but blocks new invites
+    pub fn set_max_members(ctx: Context<UpdateSubscriptionPlan>, max_members: u16) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        plan.max_members = max_members;
+
+        emit!(MaxMembersUpdated {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            max_members,
+        });
+
+        Ok(())
+    }
+
+    /// Invite a wallet to share a group plan subscription (Subscriber function)
+    /// The invite takes a member slot right away and grants access once accepted
+    pub fn invite_member(ctx: Context<InviteMember>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &ctx.accounts.subscription_plan;
+        let member = ctx.accounts.member.key();
+
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+        require!(member != subscription.subscriber, SubscriptionError::CannotInviteSelf);
+        require!(
+            subscription.member_count < plan.max_members,
+            SubscriptionError::MemberSlotsFull
+        );
+
+        subscription.member_count += 1;
+
+        let subscription_member = &mut ctx.accounts.subscription_member;
+        subscription_member.subscription = subscription.key();
+        subscription_member.member = member;
+        subscription_member.invited_at = Clock::get()?.unix_timestamp;
+        subscription_member.accepted_at = None;
+        subscription_member.bump = ctx.bumps.subscription_member;
+
+        emit!(MemberInvited {
+            subscription: subscription.key(),
+            subscriber: subscription.subscriber,
+            member,
+            member_count: subscription.member_count,
+        });
+
+        Ok(())
+    }
+
+    /// Accept an invite to a group plan subscription (Member function)
+    pub fn accept_membership(ctx: Context<AcceptMembership>) -> Result<()> {
+        let subscription_member = &mut ctx.accounts.subscription_member;
+        require!(
+            subscription_member.accepted_at.is_none(),
+            SubscriptionError::MembershipAlreadyAccepted
+        );
+
+        let accepted_at = Clock::get()?.unix_timestamp;
+        subscription_member.accepted_at = Some(accepted_at);
+
+        emit!(MembershipAccepted {
+            subscription: subscription_member.subscription,
+            member: subscription_member.member,
+            accepted_at,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a member or pending invite and free its slot (Subscriber or member function)
+    /// Members can remove themselves; rent goes back to the subscriber who paid it
+    pub fn remove_member(ctx: Context<RemoveMember>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.member_count = subscription.member_count.saturating_sub(1);
+
+        emit!(MemberRemoved {
+            subscription: subscription.key(),
+            member: ctx.accounts.subscription_member.member,
+            removed_by: ctx.accounts.authority.key(),
+            member_count: subscription.member_count,
+        });
+
+        Ok(())
+    }
+
+    /// Add a wallet to the provider's blocklist (Provider or operator function)
+    /// Blocked wallets cannot subscribe to any of the provider's plans
+    pub fn block_subscriber(ctx: Context<BlockSubscriber>) -> Result<()> {
+        let blocked_subscriber = &mut ctx.accounts.blocked_subscriber;
+        blocked_subscriber.provider = ctx.accounts.provider_account.authority;
+        blocked_subscriber.subscriber = ctx.accounts.subscriber.key();
+        blocked_subscriber.blocked_at = Clock::get()?.unix_timestamp;
+        blocked_subscriber.bump = ctx.bumps.blocked_subscriber;
+
+        emit!(SubscriberBlocked {
+            provider: blocked_subscriber.provider,
+            subscriber: blocked_subscriber.subscriber,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a wallet from the provider's blocklist (Provider or operator function)
+    pub fn unblock_subscriber(ctx: Context<UnblockSubscriber>) -> Result<()> {
+        emit!(SubscriberUnblocked {
+            provider: ctx.accounts.provider_account.authority,
+            subscriber: ctx.accounts.blocked_subscriber.subscriber,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a blocked wallet's subscription (Provider function)
+    /// The unused paid time is refunded from the provider vault to whoever paid for it: the
+    /// subscriber's share to their token account, and the share an organization or gifter
+    /// paid to the organization vault or the gifter
+    pub fn force_cancel_subscription(ctx: Context<ForceCancelSubscription>) -> Result<()> {
+        let clock = Clock::get()?;
+        let accounts = ctx.accounts;
+        let (refund_amount, sponsor_refund_amount) = cancel_with_refund(
+            &mut accounts.subscription,
+            &mut accounts.subscription_plan,
+            &mut accounts.provider_account,
+            &accounts.provider_vault,
+            accounts.subscriber_token_account.as_ref().map(|account| account.to_account_info()),
+            accounts.sponsor_token_account.as_ref().map(|account| account.to_account_info()),
+            accounts.token_program.to_account_info(),
+        )?;
+
+        emit!(SubscriptionForceCancelled {
+            provider: accounts.subscription_plan.provider,
+            subscriber: accounts.subscription.subscriber,
+            subscription_plan: accounts.subscription.subscription_plan,
+            refund_amount,
+            sponsor_refund_amount,
+            cancelled_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel any subscription to one of the provider's plans (Provider function)
+    /// Ends service immediately and refunds the unused paid time to whoever paid for it.
+    /// This is for terms-of-service violations and is deliberately exempt from the plan's
+    /// notice date: the refund stands in for notice. Ending service for ordinary reasons
+    /// goes through `sunset_plan`, which keeps subscriptions running until `sunset_at`.
+    pub fn provider_cancel_subscription(ctx: Context<ProviderCancelSubscription>) -> Result<()> {
+        let clock = Clock::get()?;
+        let accounts = ctx.accounts;
+        let (refund_amount, sponsor_refund_amount) = cancel_with_refund(
+            &mut accounts.subscription,
+            &mut accounts.subscription_plan,
+            &mut accounts.provider_account,
+            &accounts.provider_vault,
+            accounts.subscriber_token_account.as_ref().map(|account| account.to_account_info()),
+            accounts.sponsor_token_account.as_ref().map(|account| account.to_account_info()),
+            accounts.token_program.to_account_info(),
+        )?;
+
+        emit!(SubscriptionCancelledByProvider {
+            provider: accounts.subscription_plan.provider,
+            subscriber: accounts.subscription.subscriber,
+            subscription_plan: accounts.subscription.subscription_plan,
+            refund_amount,
+            sponsor_refund_amount,
+            cancelled_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Schedule a plan to be retired (Provider function)
+    /// New subscriptions are refused immediately; existing ones keep renewing for periods
+    /// that end by `sunset_at` and are then ended one by one with `expire_sunset_subscription`
+    pub fn sunset_plan(ctx: Context<UpdateSubscriptionPlan>, sunset_at: i64) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        let clock = Clock::get()?;
+
+        require!(plan.sunset_at.is_none(), SubscriptionError::PlanSunset);
+        let earliest_sunset = clock.unix_timestamp
+            .checked_add(MIN_SUNSET_NOTICE)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        require!(sunset_at >= earliest_sunset, SubscriptionError::InvalidSunsetDate);
+
+        plan.sunset_at = Some(sunset_at);
+
+        emit!(PlanSunsetScheduled {
+            provider: plan.provider,
+            subscription_plan: plan.key(),
+            sunset_at,
+        });
+
+        Ok(())
+    }
+
+    /// End a subscription whose plan has reached its sunset date (Can be called by anyone - automated)
+    /// Burns the subscriber's membership token when the plan issues them
+    pub fn expire_sunset_subscription(ctx: Context<ExpireSunsetSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &mut ctx.accounts.subscription_plan;
+        let clock = Clock::get()?;
+
+        let sunset_at = plan.sunset_at.ok_or(SubscriptionError::PlanNotSunset)?;
+        require!(clock.unix_timestamp >= sunset_at, SubscriptionError::PlanNotSunset);
+        require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+
+        subscription.is_active = false;
+        subscription.cancelled_at = Some(clock.unix_timestamp);
+
+        plan.current_subscribers = plan.current_subscribers.saturating_sub(1);
+        plan.current_seats = plan.current_seats.saturating_sub(subscription.quantity);
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.active_subscribers = provider_account.active_subscribers.saturating_sub(1);
+
+        revoke_membership_token_if_enabled(
+            plan,
+            ctx.accounts.membership_mint.as_ref(),
+            ctx.accounts.membership_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+        )?;
+
+        emit!(SubscriptionSunset {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            sunset_at,
+            ended_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Propose a new owner for a plan (Provider function)
+    /// The plan and vault addresses do not change; only the authorized wallet does
+    pub fn propose_plan_owner(ctx: Context<UpdateSubscriptionPlan>, new_owner: Pubkey) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        require!(
+            new_owner != Pubkey::default() && new_owner != plan.owner,
+            SubscriptionError::Unauthorized
+        );
+
+        plan.pending_owner = Some(new_owner);
+
+        emit!(PlanOwnershipProposed {
+            subscription_plan: plan.key(),
+            owner: plan.owner,
+            proposed_owner: new_owner,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a pending plan ownership transfer (Provider function)
+    pub fn cancel_plan_ownership_proposal(ctx: Context<UpdateSubscriptionPlan>) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        let cancelled_owner = plan.pending_owner
+            .take()
+            .ok_or(SubscriptionError::NoPendingOwner)?;
+
+        emit!(PlanOwnershipProposalCancelled {
+            subscription_plan: plan.key(),
+            owner: plan.owner,
+            cancelled_owner,
+        });
+
+        Ok(())
+    }
+
+    /// Accept a pending plan ownership transfer (Proposed owner function)
+    /// Moves the plan and its active subscribers to the new owner's provider account
+    pub fn accept_plan_ownership(ctx: Context<AcceptPlanOwnership>) -> Result<()> {
+        let plan = &mut ctx.accounts.subscription_plan;
+        let previous_provider_account = &mut ctx.accounts.previous_provider_account;
+        let new_provider_account = &mut ctx.accounts.new_provider_account;
+        let manager = &mut ctx.accounts.subscription_manager;
+
+        if new_provider_account.authority == Pubkey::default() {
+            new_provider_account.initialize(
+                ctx.accounts.new_owner.key(),
+                String::new(),
+                String::new(),
+                Clock::get()?.unix_timestamp,
+                ctx.bumps.new_provider_account,
+            );
+
+            manager.total_providers = manager.total_providers
+                .checked_add(1)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+            emit!(ProviderRegistered {
+                provider: new_provider_account.authority,
+                name: new_provider_account.name.clone(),
+                metadata_uri: new_provider_account.metadata_uri.clone(),
+            });
+        }
+
+        previous_provider_account.plan_count = previous_provider_account.plan_count.saturating_sub(1);
+        previous_provider_account.active_subscribers = previous_provider_account.active_subscribers
+            .saturating_sub(plan.current_subscribers);
+        new_provider_account.plan_count = new_provider_account.plan_count
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        new_provider_account.active_subscribers = new_provider_account.active_subscribers
+            .checked_add(plan.current_subscribers)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        let previous_owner = plan.owner;
+        plan.owner = ctx.accounts.new_owner.key();
+        plan.pending_owner = None;
+
+        emit!(PlanOwnershipTransferred {
+            subscription_plan: plan.key(),
+            previous_owner,
+            new_owner: plan.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Create an organization that pays for its members' subscriptions (Admin function)
+    /// The organization is funded by transferring tokens into its vault
+    pub fn create_organization(
+        ctx: Context<CreateOrganization>,
+        org_id: String,
+        name: String,
+    ) -> Result<()> {
+        require!(org_id.len() <= MAX_PLAN_ID_LENGTH, SubscriptionError::PlanIdTooLong);
+        require!(name.len() <= MAX_NAME_LENGTH, SubscriptionError::NameTooLong);
+
+        let organization = &mut ctx.accounts.organization;
+        organization.admin = ctx.accounts.admin.key();
+        organization.org_id = org_id;
+        organization.name = name;
+        organization.payment_token = ctx.accounts.payment_token_mint.key();
+        organization.member_count = 0;
+        organization.total_spent = 0;
+        organization.created_at = Clock::get()?.unix_timestamp;
+        organization.bump = ctx.bumps.organization;
+
+        emit!(OrganizationCreated {
+            organization: organization.key(),
+            admin: organization.admin,
+            org_id: organization.org_id.clone(),
+            payment_token: organization.payment_token,
+        });
+
+        Ok(())
+    }
+
+    /// Add a member whose subscriptions the organization will pay for (Admin function)
+    pub fn add_organization_member(
+        ctx: Context<AddOrganizationMember>,
+        spending_cap: Option<u64>,
+    ) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        let organization_member = &mut ctx.accounts.organization_member;
+
+        organization_member.organization = organization.key();
+        organization_member.member = ctx.accounts.member.key();
+        organization_member.spending_cap = spending_cap;
+        organization_member.total_spent = 0;
+        organization_member.added_at = Clock::get()?.unix_timestamp;
+        organization_member.bump = ctx.bumps.organization_member;
+
+        organization.member_count = organization.member_count
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(OrganizationMemberAdded {
+            organization: organization.key(),
+            member: organization_member.member,
+            spending_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Change a member's spending cap (Admin function)
+    pub fn update_organization_member(
+        ctx: Context<UpdateOrganizationMember>,
+        spending_cap: Option<u64>,
+    ) -> Result<()> {
+        let organization_member = &mut ctx.accounts.organization_member;
+        organization_member.spending_cap = spending_cap;
+
+        emit!(OrganizationMemberUpdated {
+            organization: organization_member.organization,
+            member: organization_member.member,
+            spending_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a member (Admin function)
+    /// Their sponsored subscriptions can no longer be charged to the organization
+    pub fn remove_organization_member(ctx: Context<RemoveOrganizationMember>) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        organization.member_count = organization.member_count.saturating_sub(1);
+
+        emit!(OrganizationMemberRemoved {
+            organization: organization.key(),
+            member: ctx.accounts.organization_member.member,
+        });
+
+        Ok(())
+    }
+
+    /// Have the organization pay for one of the member's subscriptions (Member and Admin function)
+    /// Both sign, so the organization only pays for subscriptions its admin approved.
+    /// While sponsored, the member cannot add seats or add-ons to raise the organization's bill.
+    pub fn assign_organization_subscription(ctx: Context<AssignOrganizationSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let organization_key = ctx.accounts.organization.key();
+
+        subscription.organization = Some(organization_key);
+        subscription.payer = organization_key;
+
+        emit!(OrganizationSubscriptionAssigned {
+            organization: organization_key,
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+        });
+
+        Ok(())
+    }
+
+    /// Take back payment of a sponsored subscription (Member function)
+    /// Also works after the member has been removed from the organization
+    pub fn unassign_organization_subscription(ctx: Context<UnassignOrganizationSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let organization = subscription.organization
+            .take()
+            .ok_or(SubscriptionError::Unauthorized)?;
+        subscription.payer = subscription.subscriber;
+
+        emit!(OrganizationSubscriptionUnassigned {
+            organization,
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+        });
+
+        Ok(())
+    }
+
+    /// Charge a sponsored subscription to the organization vault (Can be called by anyone - automated)
+    pub fn process_organization_payment(ctx: Context<ProcessOrganizationPayment>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &mut ctx.accounts.subscription_plan;
+        let organization = &mut ctx.accounts.organization;
+        let organization_member = &mut ctx.accounts.organization_member;
+        let clock = Clock::get()?;
+
+        subscription.require_renewal_due(plan, clock.unix_timestamp)?;
+
+        let breakdown = subscription.payment_breakdown(plan)?;
+        let amount = breakdown.amount;
+        let member_total_spent = organization_member.total_spent
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        if let Some(spending_cap) = organization_member.spending_cap {
+            require!(member_total_spent <= spending_cap, SubscriptionError::SpendingCapExceeded);
+        }
+        require!(
+            ctx.accounts.organization_vault.amount >= amount,
+            SubscriptionError::InsufficientFunds
+        );
+
+        // The organization vault is owned by the organization PDA
+        let admin_key = organization.admin;
+        let organization_seeds = &[
+            ORGANIZATION_SEED,
+            admin_key.as_ref(),
+            organization.org_id.as_bytes(),
+            &[organization.bump],
+        ];
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.organization_vault.to_account_info(),
+            to: ctx.accounts.provider_vault.to_account_info(),
+            authority: organization.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+                &[organization_seeds],
+            ),
+            amount,
+        )?;
+
+        subscription.record_payment(plan, &breakdown, clock.unix_timestamp)?;
+
+        organization_member.total_spent = member_total_spent;
+        organization.total_spent = organization.total_spent
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        plan.total_revenue = plan.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let provider_account = &mut ctx.accounts.provider_account;
+        provider_account.total_revenue = provider_account.total_revenue
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+
+        emit!(PaymentProcessed {
+            subscriber: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            amount,
+            base_amount: breakdown.base_amount,
+            usage_units: breakdown.usage_units,
+            usage_amount: breakdown.usage_amount,
+            credit_applied: breakdown.credit_applied,
+            add_on_amount: breakdown.add_on_amount,
+            add_ons: subscription.add_ons.clone(),
+            payment_number: subscription.total_payments_made,
+            payment_nonce: subscription.payment_nonce,
+            next_payment_due: subscription.next_payment_due,
+        });
+        emit!(OrganizationPaymentProcessed {
+            organization: organization.key(),
+            member: subscription.subscriber,
+            subscription_plan: subscription.subscription_plan,
+            amount,
+            member_total_spent,
+            organization_total_spent: organization.total_spent,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw unused funds from the organization vault (Admin function)
+    pub fn withdraw_organization_funds(ctx: Context<WithdrawOrganizationFunds>, amount: u64) -> Result<()> {
+        let organization = &ctx.accounts.organization;
+
+        require!(amount > 0, SubscriptionError::InvalidPrice);
+        require!(
+            ctx.accounts.organization_vault.amount >= amount,
+            SubscriptionError::InsufficientFunds
+        );
+
+        let admin_key = organization.admin;
+        let organization_seeds = &[
+            ORGANIZATION_SEED,
+            admin_key.as_ref(),
+            organization.org_id.as_bytes(),
+            &[organization.bump],
+        ];
+
+        let transfer_instruction = Transfer {
+            from: ctx.accounts.organization_vault.to_account_info(),
+            to: ctx.accounts.admin_token_account.to_account_info(),
+            authority: organization.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_instruction,
+                &[organization_seeds],
+            ),
+            amount,
+        )?;
+
+        emit!(OrganizationFundsWithdrawn {
+            organization: organization.key(),
+            admin: organization.admin,
+            amount,
         });
 
         Ok(())
     }
 
     /// Withdraw funds from the provider vault
-    /// Allows providers to withdraw accumulated revenue from their vault
+    /// Allows providers (or their treasurer) to withdraw accumulated revenue from their vault
+    /// to the plan owner's token account
     pub fn withdraw_funds(ctx: Context<WithdrawFunds>, amount: u64) -> Result<()> {
         let plan = &ctx.accounts.subscription_plan;
         let provider_vault = &ctx.accounts.provider_vault;
@@ -239,11 +2546,13 @@ pub mod subs3 {
         require!(vault_balance >= amount, SubscriptionError::InsufficientFunds);
 
         // Prepare signer seeds for PDA authority
-        let provider_key = provider.key();
+        // The vault is owned by the plan PDA, which is seeded by the original
+        // provider even after an ownership transfer
+        let provider_key = plan.provider;
         let plan_id_bytes = plan.plan_id.as_bytes();
-        let bump = ctx.bumps.provider_vault;
+        let bump = plan.bump;
         let signer_seeds = &[
-            PROVIDER_VAULT_SEED,
+            SUBSCRIPTION_PLAN_SEED,
             provider_key.as_ref(),
             plan_id_bytes,
             &[bump],
@@ -254,7 +2563,7 @@ pub mod subs3 {
         let transfer_instruction = Transfer {
             from: provider_vault.to_account_info(),
             to: ctx.accounts.provider_token_account.to_account_info(),
-            authority: provider_vault.to_account_info(),
+            authority: plan.to_account_info(),
         };
 
         token::transfer(
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use crate::util::constants::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct AddOn {
+    pub subscription_plan: Pubkey,
+    #[max_len(MAX_PLAN_ID_LENGTH)]
+    pub add_on_id: String,
+    #[max_len(MAX_NAME_LENGTH)]
+    pub name: String,
+    pub price_per_period: u64,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// An add-on attached to a subscription, with its price locked in at attach time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AttachedAddOn {
+    pub add_on: Pubkey,
+    pub price_per_period: u64,
+}
@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+/// Marks a payment token mint as approved for new plans
+#[account]
+#[derive(InitSpace)]
+pub struct AllowedMint {
+    pub mint: Pubkey,
+    pub added_by: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
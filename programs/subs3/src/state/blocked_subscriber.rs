@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+
+/// A wallet a provider refuses to serve. Its existence blocks new subscriptions
+/// to any of the provider's plans.
+#[account]
+#[derive(InitSpace)]
+pub struct BlockedSubscriber {
+    pub provider: Pubkey,
+    pub subscriber: Pubkey,
+    pub blocked_at: i64,
+    pub bump: u8,
+}
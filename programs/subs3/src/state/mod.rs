@@ -1,7 +1,23 @@
 pub mod subscription_manager;
 pub mod subscription_plan;
 pub mod subscription;
+pub mod add_on;
+pub mod allowed_mint;
+pub mod provider;
+pub mod verifier;
+pub mod blocked_subscriber;
+pub mod organization;
+pub mod subscription_member;
+pub mod receipt;
 
 pub use subscription_manager::*;
 pub use subscription_plan::*;
 pub use subscription::*;
+pub use add_on::*;
+pub use allowed_mint::*;
+pub use provider::*;
+pub use verifier::*;
+pub use blocked_subscriber::*;
+pub use organization::*;
+pub use subscription_member::*;
+pub use receipt::*;
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::util::constants::*;
+
+/// Sponsor that pays for its members' subscriptions from an escrow vault
+#[account]
+#[derive(InitSpace)]
+pub struct Organization {
+    pub admin: Pubkey,
+    #[max_len(MAX_PLAN_ID_LENGTH)]
+    pub org_id: String,
+    #[max_len(MAX_NAME_LENGTH)]
+    pub name: String,
+    pub payment_token: Pubkey,
+    pub member_count: u32,
+    pub total_spent: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OrganizationMember {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    /// Maximum the organization will spend on this member in total, if any
+    pub spending_cap: Option<u64>,
+    pub total_spent: u64,
+    pub added_at: i64,
+    pub bump: u8,
+}
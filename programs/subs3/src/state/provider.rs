@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use crate::util::constants::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Provider {
+    pub authority: Pubkey,
+    #[max_len(MAX_NAME_LENGTH)]
+    pub name: String,
+    #[max_len(MAX_METADATA_URI_LENGTH)]
+    pub metadata_uri: String,
+    pub plan_count: u32,
+    pub total_revenue: u64,
+    pub active_subscribers: u32,
+    pub registered_at: i64,
+    pub bump: u8,
+    /// 0 when unverified, otherwise up to `MAX_VERIFICATION_LEVEL`
+    pub verification_level: u8,
+    pub verified_by: Option<Pubkey>,
+    pub verification_expires_at: Option<i64>,
+    /// Keys the provider has delegated scoped permissions to
+    #[max_len(MAX_PROVIDER_ROLES)]
+    pub roles: Vec<RoleAssignment>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ProviderRole {
+    /// May withdraw plan revenue to the owner's token account
+    Treasurer,
+    /// May report usage, edit plan details, and manage the blocklist
+    Operator,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct RoleAssignment {
+    pub member: Pubkey,
+    pub role: ProviderRole,
+}
+
+impl Provider {
+    pub fn initialize(
+        &mut self,
+        authority: Pubkey,
+        name: String,
+        metadata_uri: String,
+        registered_at: i64,
+        bump: u8,
+    ) {
+        self.authority = authority;
+        self.name = name;
+        self.metadata_uri = metadata_uri;
+        self.plan_count = 0;
+        self.total_revenue = 0;
+        self.active_subscribers = 0;
+        self.registered_at = registered_at;
+        self.bump = bump;
+        self.verification_level = 0;
+        self.verified_by = None;
+        self.verification_expires_at = None;
+        self.roles = Vec::new();
+    }
+
+    /// The owner may do everything; other keys need the matching role
+    pub fn is_authorized(&self, key: Pubkey, role: ProviderRole) -> bool {
+        key == self.authority
+            || self.roles.iter().any(|assignment| assignment.member == key && assignment.role == role)
+    }
+
+    pub fn is_verified(&self, now: i64) -> bool {
+        if self.verification_level == 0 {
+            return false;
+        }
+        match self.verification_expires_at {
+            Some(expires_at) => now < expires_at,
+            None => true,
+        }
+    }
+}
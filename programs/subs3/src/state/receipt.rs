@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+/// On-chain record of a single renewal, optionally created by `process_payment`
+#[account]
+#[derive(InitSpace)]
+pub struct Receipt {
+    pub subscription: Pubkey,
+    pub subscriber: Pubkey,
+    pub subscription_plan: Pubkey,
+    /// Subscription `payment_nonce` before this payment; part of the receipt address
+    pub payment_nonce: u64,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub base_amount: u64,
+    pub usage_units: u64,
+    pub usage_amount: u64,
+    pub add_on_amount: u64,
+    pub credit_applied: u64,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub paid_at: i64,
+    pub bump: u8,
+}
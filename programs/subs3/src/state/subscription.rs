@@ -1,4 +1,8 @@
 use anchor_lang::prelude::*;
+use crate::error::SubscriptionError;
+use crate::state::{AttachedAddOn, SubscriptionPlan};
+use crate::util::pricing::{prorate, unused_share};
+use crate::util::constants::*;
 
 #[account]
 #[derive(InitSpace)]
@@ -15,4 +19,743 @@ pub struct Subscription {
     pub total_amount_paid: u64,
     pub payment_nonce: u64,
     pub bump: u8,
+    pub prepaid_through: Option<i64>,
+    pub current_period_usage: u64,
+    pub quantity: u32,
+    /// Prorated credit from seat decreases, applied to upcoming payments
+    pub credit_balance: u64,
+    #[max_len(MAX_ADD_ONS_PER_SUBSCRIPTION)]
+    pub add_ons: Vec<AttachedAddOn>,
+    /// Wallet funding the subscription; differs from `subscriber` while a gift is active
+    pub payer: Pubkey,
+    /// Whether the subscriber takes over payments once gifted periods run out
+    pub renews_after_gift: bool,
+    /// Organization paying for this subscription, if any
+    pub organization: Option<Pubkey>,
+    /// Member slots in use on a group plan, pending invites included
+    pub member_count: u16,
+    /// Most recent payments, oldest overwritten first once full
+    #[max_len(PAYMENT_HISTORY_LENGTH)]
+    pub payment_history: Vec<PaymentRecord>,
+    /// Slot in `payment_history` the next payment overwrites once the buffer is full
+    pub payment_history_cursor: u8,
+    /// Subscriber-set limits checked by `process_payment` before every charge
+    pub spending_limits: SpendingLimits,
+    /// Day (unix time / `SPENDING_BUCKET_SECONDS`) of the latest charge in `spending_buckets`
+    pub spending_day: i64,
+    /// Amount charged on each of the last `SPENDING_BUCKETS` days, indexed by day
+    /// modulo the bucket count
+    pub spending_buckets: [u64; SPENDING_BUCKETS],
+    /// Amount paid for service from `paid_from` to `paid_until`, the period in progress
+    pub paid_amount: u64,
+    pub paid_from: i64,
+    pub paid_until: i64,
+    /// Amount paid for prepaid time from `paid_until` to `next_payment_due`
+    pub prepaid_amount: u64,
+    /// Part of `paid_amount` the subscriber paid themselves; `paid_sponsor` paid the rest.
+    /// Prepaid time is always paid by the subscriber.
+    pub paid_by_subscriber: u64,
+    pub paid_sponsor: Option<Sponsor>,
+}
+
+/// Who paid for a subscription on the subscriber's behalf
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum Sponsor {
+    Gifter(Pubkey),
+    Organization(Pubkey),
+}
+
+/// Limits a subscriber places on renewals pulled from their wallet
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct SpendingLimits {
+    pub max_per_charge: Option<u64>,
+    /// Maximum charged within any rolling `SPENDING_WINDOW_DAYS` days. Charges are counted
+    /// by day, so each one counts until the end of the `SPENDING_WINDOW_DAYS`th day after it.
+    pub max_per_window: Option<u64>,
+    /// Renewals stop being charged from this time on
+    pub authorized_until: Option<i64>,
+}
+
+/// Outcome of one item in `process_payments_batch`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BatchPaymentStatus {
+    Paid,
+    /// Not due yet, or not renewing (inactive, paused, prepaid, or plan sunset)
+    NotDue,
+    /// Paid through an organization, or a gift that does not renew
+    Ineligible,
+    SpendingLimit,
+    /// Subscription's payment delegate is not approved for the amount due
+    NotDelegated,
+    InsufficientFunds,
+}
+
+/// Which spending limit stopped a renewal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpendingLimitKind {
+    PerCharge,
+    Window,
+    AuthorizationExpired,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PaymentRecord {
+    pub paid_at: i64,
+    pub amount: u64,
+    /// `payment_nonce` before the payment, matching its `Receipt` address
+    pub payment_nonce: u64,
+}
+
+/// Itemized amount charged by a renewal
+#[derive(Clone, Copy)]
+pub struct PaymentBreakdown {
+    pub base_amount: u64,
+    pub usage_units: u64,
+    pub usage_amount: u64,
+    pub add_on_amount: u64,
+    pub credit_applied: u64,
+    pub amount: u64,
+}
+
+impl Subscription {
+    pub fn initialize(
+        &mut self,
+        subscriber: Pubkey,
+        subscription_plan: Pubkey,
+        start_time: i64,
+        next_payment_due: i64,
+        quantity: u32,
+        bump: u8,
+    ) {
+        self.subscriber = subscriber;
+        self.subscription_plan = subscription_plan;
+        self.start_time = start_time;
+        self.next_payment_due = next_payment_due;
+        self.is_active = true;
+        self.is_paused = false;
+        self.total_payments_made = 0;
+        self.total_amount_paid = 0;
+        self.payment_nonce = 0;
+        self.bump = bump;
+        self.prepaid_through = None;
+        self.current_period_usage = 0;
+        self.quantity = quantity;
+        self.credit_balance = 0;
+        self.add_ons = Vec::new();
+        self.payer = subscriber;
+        self.renews_after_gift = false;
+        self.organization = None;
+        self.member_count = 0;
+        self.payment_history = Vec::new();
+        self.payment_history_cursor = 0;
+        self.spending_limits = SpendingLimits::default();
+        self.spending_day = 0;
+        self.spending_buckets = [0; SPENDING_BUCKETS];
+        // Nothing is paid for the first period until a charge says otherwise
+        self.paid_amount = 0;
+        self.paid_from = start_time;
+        self.paid_until = next_payment_due;
+        self.prepaid_amount = 0;
+        self.paid_by_subscriber = 0;
+        self.paid_sponsor = None;
+    }
+
+    /// Fill in fields added after an account was created whose zero value is not a valid
+    /// default: a zero seat count bills nothing and a zero payer reads as an ended gift
+    pub fn apply_migration_defaults(&mut self) {
+        if self.quantity == 0 {
+            self.quantity = 1;
+        }
+        if self.payer == Pubkey::default() {
+            self.payer = self.subscriber;
+        }
+    }
+
+    /// Amount charged in the `SPENDING_BUCKETS` days up to and including the day of `now`
+    pub fn spent_in_window(&self, now: i64) -> u64 {
+        let window = SPENDING_BUCKETS as i64;
+        let today = now.div_euclid(SPENDING_BUCKET_SECONDS);
+        let first_day = (today - window + 1).max(self.spending_day - window + 1);
+        (first_day..=self.spending_day)
+            .map(|day| self.spending_buckets[day.rem_euclid(window) as usize])
+            .fold(0, u64::saturating_add)
+    }
+
+    /// First spending limit that a charge of `amount` at `now` would break, if any
+    pub fn exceeded_spending_limit(&self, amount: u64, now: i64) -> Option<SpendingLimitKind> {
+        let limits = &self.spending_limits;
+        if let Some(authorized_until) = limits.authorized_until {
+            if now >= authorized_until {
+                return Some(SpendingLimitKind::AuthorizationExpired);
+            }
+        }
+        if let Some(max_per_charge) = limits.max_per_charge {
+            if amount > max_per_charge {
+                return Some(SpendingLimitKind::PerCharge);
+            }
+        }
+        if let Some(max_per_window) = limits.max_per_window {
+            match self.spent_in_window(now).checked_add(amount) {
+                Some(total) if total <= max_per_window => {}
+                _ => return Some(SpendingLimitKind::Window),
+            }
+        }
+        None
+    }
+
+    /// Count a charge against the spending window, clearing buckets of days that left it
+    pub fn record_spending(&mut self, amount: u64, now: i64) -> Result<()> {
+        let window = SPENDING_BUCKETS as i64;
+        let today = now.div_euclid(SPENDING_BUCKET_SECONDS);
+        if today > self.spending_day {
+            for day in (self.spending_day + 1).max(today - window + 1)..=today {
+                self.spending_buckets[day.rem_euclid(window) as usize] = 0;
+            }
+            self.spending_day = today;
+        }
+        let bucket = &mut self.spending_buckets[self.spending_day.rem_euclid(window) as usize];
+        *bucket = bucket
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Whether the subscription currently grants access: active, not paused, and paid
+    /// up to `next_payment_due` (within the payment grace period)
+    pub fn is_entitled(&self, now: i64) -> bool {
+        self.is_active
+            && !self.is_paused
+            && now < self.next_payment_due.saturating_add(PAYMENT_GRACE_PERIOD)
+    }
+
+    pub fn is_gift(&self) -> bool {
+        self.payer != self.subscriber && self.organization.is_none()
+    }
+
+    /// Whether a token account returns refunds to whoever sponsored the period in progress:
+    /// the organization's vault, or a token account of the gifter
+    pub fn is_sponsor_refund_account(&self, key: &Pubkey, owner: &Pubkey) -> bool {
+        match self.paid_sponsor {
+            Some(Sponsor::Organization(organization)) => {
+                let (organization_vault, _) = Pubkey::find_program_address(
+                    &[ORGANIZATION_VAULT_SEED, organization.as_ref()],
+                    &crate::ID,
+                );
+                *key == organization_vault
+            }
+            Some(Sponsor::Gifter(gifter)) => *owner == gifter,
+            None => false,
+        }
+    }
+
+    /// Checks shared by every renewal path
+    pub fn require_renewal_due(&self, plan: &SubscriptionPlan, now: i64) -> Result<()> {
+        require!(self.is_active, SubscriptionError::SubscriptionInactive);
+        require!(!self.is_paused, SubscriptionError::SubscriptionPaused);
+        require!(
+            now >= self.next_payment_due - PAYMENT_GRACE_PERIOD,
+            SubscriptionError::PaymentNotDue
+        );
+        // Plans being sunset only renew for periods that end by the sunset date, so no
+        // subscriber pays for time the plan will not serve
+        if let Some(sunset_at) = plan.sunset_at {
+            let period_end = self.next_payment_due
+                .checked_add(plan.period_duration_seconds)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            require!(period_end <= sunset_at, SubscriptionError::PlanSunset);
+        }
+        // Prepaid subscriptions are skipped until the prepaid window ends
+        if let Some(prepaid_through) = self.prepaid_through {
+            require!(now >= prepaid_through, SubscriptionError::SubscriptionPrepaid);
+        }
+        Ok(())
+    }
+
+    /// Per-period price of all attached add-ons
+    pub fn add_on_total(&self) -> Result<u64> {
+        self.add_ons
+            .iter()
+            .try_fold(0u64, |total, add_on| total.checked_add(add_on.price_per_period))
+            .ok_or(error!(SubscriptionError::ArithmeticOverflow))
+    }
+
+    /// Amount due is the per-seat price for every seat plus any metered usage
+    /// for the period and attached add-ons, less any credit from earlier seat decreases
+    pub fn payment_breakdown(&self, plan: &SubscriptionPlan) -> Result<PaymentBreakdown> {
+        let base_amount = plan.price_per_period
+            .checked_mul(self.quantity as u64)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let usage_units = self.current_period_usage;
+        let usage_amount = match plan.metered {
+            Some(metered) => metered
+                .overage_charge(usage_units, plan.pricing_schedule.as_ref())
+                .ok_or(SubscriptionError::ArithmeticOverflow)?,
+            None => 0,
+        };
+        let add_on_amount = self.add_on_total()?;
+        let gross_amount = base_amount
+            .checked_add(usage_amount)
+            .and_then(|total| total.checked_add(add_on_amount))
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        let credit_applied = self.credit_balance.min(gross_amount);
+
+        Ok(PaymentBreakdown {
+            base_amount,
+            usage_units,
+            usage_amount,
+            add_on_amount,
+            credit_applied,
+            amount: gross_amount - credit_applied,
+        })
+    }
+
+    /// Advance the billing schedule after a renewal has been charged
+    pub fn record_payment(
+        &mut self,
+        plan: &SubscriptionPlan,
+        breakdown: &PaymentBreakdown,
+        now: i64,
+    ) -> Result<()> {
+        self.push_payment_history(PaymentRecord {
+            paid_at: now,
+            amount: breakdown.amount,
+            payment_nonce: self.payment_nonce,
+        });
+        // The renewal pays for the coming period; usage is billed for the one that ended
+        self.paid_amount = breakdown.base_amount
+            .checked_add(breakdown.add_on_amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        self.paid_sponsor = match self.organization {
+            Some(organization) => Some(Sponsor::Organization(organization)),
+            None if self.payer != self.subscriber => Some(Sponsor::Gifter(self.payer)),
+            None => None,
+        };
+        self.paid_by_subscriber = if self.paid_sponsor.is_some() { 0 } else { self.paid_amount };
+        self.paid_from = self.next_payment_due;
+        self.prepaid_amount = 0;
+        self.next_payment_due = self.next_payment_due
+            .checked_add(plan.period_duration_seconds)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        self.paid_until = self.next_payment_due;
+        self.total_payments_made = self.total_payments_made
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        self.total_amount_paid = self.total_amount_paid
+            .checked_add(breakdown.amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        self.payment_nonce = self.payment_nonce
+            .checked_add(1)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        // Usage has been billed, start metering the next period from zero
+        self.current_period_usage = 0;
+        self.credit_balance -= breakdown.credit_applied;
+        Ok(())
+    }
+
+    fn push_payment_history(&mut self, record: PaymentRecord) {
+        if self.payment_history.len() < PAYMENT_HISTORY_LENGTH {
+            self.payment_history.push(record);
+        } else {
+            let cursor = self.payment_history_cursor as usize % PAYMENT_HISTORY_LENGTH;
+            self.payment_history[cursor] = record;
+            self.payment_history_cursor = ((cursor + 1) % PAYMENT_HISTORY_LENGTH) as u8;
+        }
+    }
+
+    /// Paid value of the time left at `now`: the rest of the period in progress at the
+    /// amount paid for it, plus prepaid time at the bundle price. Returns `None` on overflow.
+    pub fn unused_value(&self, now: i64) -> Option<u64> {
+        unused_share(self.paid_amount, self.paid_from, self.paid_until, now)?
+            .checked_add(unused_share(self.prepaid_amount, self.paid_until, self.next_payment_due, now)?)
+    }
+
+    /// `unused_value` split into the part the subscriber paid and the part `paid_sponsor` paid
+    pub fn unused_value_by_payer(&self, now: i64) -> Option<(u64, u64)> {
+        let total = self.unused_value(now)?;
+        let subscriber = unused_share(self.paid_by_subscriber, self.paid_from, self.paid_until, now)?
+            .checked_add(unused_share(self.prepaid_amount, self.paid_until, self.next_payment_due, now)?)?;
+        Some((subscriber, total - subscriber))
+    }
+
+    /// Drop the paid value already used up at `now`, so a new payment is not spread over past time
+    fn settle_paid_value(&mut self, now: i64) -> Result<()> {
+        if now >= self.paid_until {
+            self.prepaid_amount = unused_share(self.prepaid_amount, self.paid_until, self.next_payment_due, now)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            self.paid_amount = 0;
+            self.paid_by_subscriber = 0;
+            self.paid_from = now;
+            self.paid_until = now;
+        } else if now > self.paid_from {
+            self.paid_amount = unused_share(self.paid_amount, self.paid_from, self.paid_until, now)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            self.paid_by_subscriber = unused_share(self.paid_by_subscriber, self.paid_from, self.paid_until, now)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?;
+            self.paid_from = now;
+        }
+        Ok(())
+    }
+
+    /// Record `amount` the subscriber paid at `now` for prepaid time added after the
+    /// current `next_payment_due`
+    pub fn add_prepaid_value(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.settle_paid_value(now)?;
+        self.prepaid_amount = self.prepaid_amount
+            .checked_add(amount)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Record `amount` the subscriber paid at `now` for the time left until
+    /// `next_payment_due`, e.g. added seats, split by time between the period in progress
+    /// and prepaid time
+    pub fn add_paid_value(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.settle_paid_value(now)?;
+        let current_share = if self.next_payment_due > now {
+            prorate(amount, self.paid_until - now.max(self.paid_from), self.next_payment_due - now)
+                .ok_or(SubscriptionError::ArithmeticOverflow)?
+        } else {
+            0
+        };
+        self.paid_amount = self.paid_amount
+            .checked_add(current_share)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        self.paid_by_subscriber = self.paid_by_subscriber
+            .checked_add(current_share)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        self.prepaid_amount = self.prepaid_amount
+            .checked_add(amount - current_share)
+            .ok_or(SubscriptionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Account whose data is the discriminator followed by `8 + space` zeroed bytes
+    fn zeroed<T: AccountDeserialize + Discriminator>(space: usize) -> T {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.resize(8 + space, 0);
+        T::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    fn plan(price_per_period: u64, period_duration_seconds: i64) -> SubscriptionPlan {
+        let mut plan: SubscriptionPlan = zeroed(SubscriptionPlan::INIT_SPACE);
+        plan.price_per_period = price_per_period;
+        plan.period_duration_seconds = period_duration_seconds;
+        plan
+    }
+
+    /// Subscription layout before any fields were added
+    #[derive(AnchorSerialize)]
+    struct BaselineSubscription {
+        subscriber: Pubkey,
+        subscription_plan: Pubkey,
+        start_time: i64,
+        next_payment_due: i64,
+        is_active: bool,
+        is_paused: bool,
+        paused_at: Option<i64>,
+        cancelled_at: Option<i64>,
+        total_payments_made: u32,
+        total_amount_paid: u64,
+        payment_nonce: u64,
+        bump: u8,
+    }
+
+    fn subscription(quantity: u32, now: i64, plan: &SubscriptionPlan) -> Subscription {
+        let mut subscription: Subscription = zeroed(Subscription::INIT_SPACE);
+        let subscriber = Pubkey::new_unique();
+        subscription.initialize(
+            subscriber,
+            Pubkey::new_unique(),
+            now,
+            now + plan.period_duration_seconds,
+            quantity,
+            255,
+        );
+        subscription
+    }
+
+    fn renew(subscription: &mut Subscription, plan: &SubscriptionPlan, now: i64) {
+        let breakdown = subscription.payment_breakdown(plan).unwrap();
+        subscription.record_payment(plan, &breakdown, now).unwrap();
+    }
+
+    #[test]
+    fn nothing_is_unused_before_the_first_charge() {
+        let plan = plan(100, 1_000);
+        let subscription = subscription(2, 0, &plan);
+        assert_eq!(subscription.unused_value(0), Some(0));
+        assert_eq!(subscription.unused_value(500), Some(0));
+    }
+
+    #[test]
+    fn renewal_is_refunded_for_the_time_left() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(2, 0, &plan);
+        renew(&mut subscription, &plan, 1_000);
+        assert_eq!(subscription.unused_value(1_000), Some(200));
+        assert_eq!(subscription.unused_value(1_500), Some(100));
+        assert_eq!(subscription.unused_value(2_000), Some(0));
+        // A late renewal still covers the period from the old due date
+        let mut late = subscription.clone();
+        renew(&mut late, &plan, 2_500);
+        assert_eq!(late.unused_value(2_500), Some(100));
+    }
+
+    #[test]
+    fn prepaid_time_is_refunded_at_the_bundle_price() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        renew(&mut subscription, &plan, 1_000);
+        // Ten periods bought for 500 at the start of a paid period
+        subscription.add_prepaid_value(500, 1_000).unwrap();
+        subscription.next_payment_due += 10_000;
+        assert_eq!(subscription.unused_value(1_000), Some(600));
+        assert_eq!(subscription.unused_value(1_500), Some(550));
+        assert_eq!(subscription.unused_value(2_000), Some(500));
+        assert_eq!(subscription.unused_value(7_000), Some(250));
+        assert_eq!(subscription.unused_value(12_000), Some(0));
+    }
+
+    #[test]
+    fn prepay_during_a_free_period_refunds_only_the_bundle() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        subscription.add_prepaid_value(500, 500).unwrap();
+        subscription.next_payment_due += 10_000;
+        assert_eq!(subscription.unused_value(500), Some(500));
+        assert_eq!(subscription.unused_value(1_000), Some(500));
+        assert_eq!(subscription.unused_value(6_000), Some(250));
+    }
+
+    #[test]
+    fn added_seats_are_refunded_for_the_time_they_cover() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        // Seat added halfway through the free period
+        subscription.add_paid_value(50, 500).unwrap();
+        assert_eq!(subscription.unused_value(500), Some(50));
+        assert_eq!(subscription.unused_value(750), Some(25));
+
+        renew(&mut subscription, &plan, 1_000);
+        subscription.add_prepaid_value(500, 1_000).unwrap();
+        subscription.next_payment_due += 10_000;
+        // Seat added halfway through the paid period, covering prepaid time at full price
+        subscription.add_paid_value(1_050, 1_500).unwrap();
+        assert_eq!(subscription.unused_value(1_500), Some(50 + 500 + 1_050));
+        assert_eq!(subscription.unused_value(2_000), Some(500 + 1_000));
+    }
+
+    #[test]
+    fn payment_history_keeps_the_latest_payments() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        for payment in 0..PAYMENT_HISTORY_LENGTH as i64 {
+            renew(&mut subscription, &plan, (payment + 1) * 1_000);
+        }
+        assert_eq!(subscription.payment_history.len(), PAYMENT_HISTORY_LENGTH);
+        assert_eq!(subscription.payment_history_cursor, 0);
+
+        // Three more payments overwrite the three oldest records
+        for payment in PAYMENT_HISTORY_LENGTH as i64..PAYMENT_HISTORY_LENGTH as i64 + 3 {
+            renew(&mut subscription, &plan, (payment + 1) * 1_000);
+        }
+        assert_eq!(subscription.payment_history.len(), PAYMENT_HISTORY_LENGTH);
+        assert_eq!(subscription.payment_history_cursor, 3);
+
+        // Reading from the cursor onwards gives the payments oldest first
+        let nonces: Vec<u64> = (0..PAYMENT_HISTORY_LENGTH)
+            .map(|offset| {
+                let slot = (subscription.payment_history_cursor as usize + offset) % PAYMENT_HISTORY_LENGTH;
+                subscription.payment_history[slot].payment_nonce
+            })
+            .collect();
+        assert_eq!(nonces, (3..PAYMENT_HISTORY_LENGTH as u64 + 3).collect::<Vec<_>>());
+        assert_eq!(subscription.payment_history[2].paid_at, 11_000);
+    }
+
+    const DAY: i64 = SPENDING_BUCKET_SECONDS;
+
+    #[test]
+    fn spending_window_rolls_by_day() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        assert_eq!(subscription.spent_in_window(0), 0);
+
+        subscription.record_spending(100, DAY - 1).unwrap();
+        subscription.record_spending(50, 10 * DAY).unwrap();
+        subscription.record_spending(25, 10 * DAY + 5).unwrap();
+        assert_eq!(subscription.spent_in_window(10 * DAY), 175);
+        // A charge counts for at least the full window, up to the end of its 30th day after
+        assert_eq!(subscription.spent_in_window(30 * DAY + DAY - 1), 175);
+        assert_eq!(subscription.spent_in_window(31 * DAY), 75);
+        assert_eq!(subscription.spent_in_window(40 * DAY + DAY - 1), 75);
+        assert_eq!(subscription.spent_in_window(41 * DAY), 0);
+    }
+
+    #[test]
+    fn record_spending_clears_days_that_left_the_window() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        for day in 0..31 {
+            subscription.record_spending(1, day * DAY).unwrap();
+        }
+        assert_eq!(subscription.spent_in_window(30 * DAY), 31);
+
+        // Two days later the two oldest buckets are reused
+        subscription.record_spending(10, 32 * DAY).unwrap();
+        assert_eq!(subscription.spent_in_window(32 * DAY), 29 + 10);
+
+        // After a long gap only the new charge is left
+        subscription.record_spending(7, 500 * DAY).unwrap();
+        assert_eq!(subscription.spent_in_window(500 * DAY), 7);
+
+        assert!(subscription.record_spending(u64::MAX, 500 * DAY).is_err());
+    }
+
+    #[test]
+    fn spending_limits_are_checked_against_the_rolling_window() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        assert_eq!(subscription.exceeded_spending_limit(u64::MAX, 0), None);
+
+        subscription.spending_limits = SpendingLimits {
+            max_per_charge: Some(100),
+            max_per_window: Some(150),
+            authorized_until: Some(100 * DAY),
+        };
+        assert_eq!(subscription.exceeded_spending_limit(100, 0), None);
+        assert_eq!(
+            subscription.exceeded_spending_limit(101, 0),
+            Some(SpendingLimitKind::PerCharge)
+        );
+
+        // A charge late in a calendar month still counts early in the next one
+        subscription.record_spending(100, 29 * DAY).unwrap();
+        assert_eq!(subscription.exceeded_spending_limit(50, 31 * DAY), None);
+        assert_eq!(
+            subscription.exceeded_spending_limit(51, 31 * DAY),
+            Some(SpendingLimitKind::Window)
+        );
+        assert_eq!(subscription.exceeded_spending_limit(100, 60 * DAY), None);
+
+        subscription.record_spending(100, 60 * DAY).unwrap();
+        assert_eq!(
+            subscription.exceeded_spending_limit(u64::MAX, 60 * DAY),
+            Some(SpendingLimitKind::PerCharge)
+        );
+        subscription.spending_limits.max_per_charge = None;
+        assert_eq!(
+            subscription.exceeded_spending_limit(u64::MAX, 60 * DAY),
+            Some(SpendingLimitKind::Window)
+        );
+        assert_eq!(
+            subscription.exceeded_spending_limit(1, 100 * DAY),
+            Some(SpendingLimitKind::AuthorizationExpired)
+        );
+    }
+
+    #[test]
+    fn sunset_plans_renew_only_whole_periods() {
+        let mut plan = plan(100, 1_000);
+        let subscription = subscription(1, 0, &plan);
+        plan.sunset_at = Some(2_000);
+        assert!(subscription.require_renewal_due(&plan, 1_000).is_ok());
+        plan.sunset_at = Some(1_999);
+        assert!(subscription.require_renewal_due(&plan, 1_000).is_err());
+        plan.sunset_at = Some(1_500);
+        assert!(subscription.require_renewal_due(&plan, 1_000).is_err());
+    }
+
+    #[test]
+    fn sponsor_refunds_return_to_whoever_paid() {
+        let plan = plan(100, 1_000);
+        let mut subscription = subscription(1, 0, &plan);
+        let other = Pubkey::new_unique();
+        assert!(!subscription.is_sponsor_refund_account(&other, &subscription.subscriber));
+
+        let gifter = Pubkey::new_unique();
+        subscription.paid_sponsor = Some(Sponsor::Gifter(gifter));
+        assert!(subscription.is_sponsor_refund_account(&other, &gifter));
+        assert!(!subscription.is_sponsor_refund_account(&other, &subscription.subscriber));
+
+        let organization = Pubkey::new_unique();
+        let (organization_vault, _) = Pubkey::find_program_address(
+            &[ORGANIZATION_VAULT_SEED, organization.as_ref()],
+            &crate::ID,
+        );
+        subscription.paid_sponsor = Some(Sponsor::Organization(organization));
+        assert!(subscription.is_sponsor_refund_account(&organization_vault, &organization));
+        assert!(!subscription.is_sponsor_refund_account(&other, &organization));
+        assert!(!subscription.is_sponsor_refund_account(&other, &gifter));
+    }
+
+    #[test]
+    fn refunds_are_split_by_who_paid() {
+        let plan = plan(100, 1_000);
+        let mut sponsored = subscription(1, 0, &plan);
+        let organization = Pubkey::new_unique();
+        sponsored.organization = Some(organization);
+        sponsored.payer = organization;
+        renew(&mut sponsored, &plan, 1_000);
+        assert_eq!(sponsored.paid_sponsor, Some(Sponsor::Organization(organization)));
+        assert_eq!(sponsored.unused_value_by_payer(1_500), Some((0, 50)));
+
+        // Seats the member adds and prepaid time are theirs
+        sponsored.add_prepaid_value(500, 1_500).unwrap();
+        sponsored.next_payment_due += 10_000;
+        sponsored.add_paid_value(1_050, 1_500).unwrap();
+        assert_eq!(sponsored.unused_value_by_payer(1_500), Some((50 + 500 + 1_000, 50)));
+        assert_eq!(sponsored.unused_value_by_payer(1_750), Some((25 + 500 + 1_000, 25)));
+        assert_eq!(sponsored.unused_value_by_payer(2_000), Some((500 + 1_000, 0)));
+
+        // Leaving the organization does not hand its share to the member
+        let mut unassigned = sponsored.clone();
+        unassigned.organization = None;
+        unassigned.payer = unassigned.subscriber;
+        assert_eq!(unassigned.unused_value_by_payer(1_500), Some((50 + 500 + 1_000, 50)));
+
+        // A self-paid renewal is all the subscriber's
+        let mut self_paid = subscription(1, 0, &plan);
+        renew(&mut self_paid, &plan, 1_000);
+        assert_eq!(self_paid.paid_sponsor, None);
+        assert_eq!(self_paid.unused_value_by_payer(1_500), Some((50, 0)));
+    }
+
+    #[test]
+    fn migrated_baseline_subscription_bills_its_subscriber() {
+        let subscriber = Pubkey::new_unique();
+        let baseline = BaselineSubscription {
+            subscriber,
+            subscription_plan: Pubkey::new_unique(),
+            start_time: 1_000,
+            next_payment_due: 2_000,
+            is_active: true,
+            is_paused: false,
+            paused_at: None,
+            cancelled_at: None,
+            total_payments_made: 3,
+            total_amount_paid: 300,
+            payment_nonce: 3,
+            bump: 254,
+        };
+        let mut data = Subscription::DISCRIMINATOR.to_vec();
+        baseline.serialize(&mut data).unwrap();
+        data.resize(8 + Subscription::INIT_SPACE, 0);
+
+        let mut subscription = Subscription::try_deserialize(&mut &data[..]).unwrap();
+        subscription.apply_migration_defaults();
+
+        assert_eq!(subscription.subscriber, subscriber);
+        assert_eq!(subscription.next_payment_due, 2_000);
+        assert_eq!(subscription.total_payments_made, 3);
+        assert!(!subscription.is_gift());
+        let breakdown = subscription.payment_breakdown(&plan(100, 1_000)).unwrap();
+        assert_eq!(breakdown.base_amount, 100);
+        assert_eq!(breakdown.amount, 100);
+    }
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::util::constants::*;
 
 #[account]
 #[derive(InitSpace)]
@@ -7,4 +8,76 @@ pub struct SubscriptionManager {
     pub total_providers: u64,
     pub total_subscriptions: u64,
     pub bump: u8,
+    pub total_plans: u64,
+    /// Proposed new authority, set by `propose_authority` until accepted or cancelled
+    pub pending_authority: Option<Pubkey>,
+    /// Global emergency switch, blocks every category
+    pub paused: bool,
+    /// Per-category pause bits (`PAUSE_*` constants)
+    pub pause_flags: u8,
+    /// Keep withdrawals and cancellations open while globally paused
+    pub allow_exits_when_paused: bool,
+    /// When false ("open" mode) plans may use any SPL Token mint
+    pub mint_allowlist_enabled: bool,
+    /// Protocol-wide bounds on the crank reward paid per renewal, in payment token units
+    pub min_crank_reward: u64,
+    pub max_crank_reward: u64,
+}
+
+impl SubscriptionManager {
+    /// Fill fields the older layout lacked. That layout counted plans in
+    /// `total_providers`; providers are recounted as plan migrations create them.
+    pub fn apply_migration_defaults(&mut self) {
+        self.total_plans = self.total_providers;
+        self.total_providers = 0;
+        self.allow_exits_when_paused = true;
+    }
+
+    pub fn is_paused(&self, category: u8) -> bool {
+        if self.pause_flags & category != 0 {
+            return true;
+        }
+        if self.paused {
+            return !(self.allow_exits_when_paused && category & PAUSE_EXITS != 0);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Manager layout before any fields were added
+    #[derive(AnchorSerialize)]
+    struct BaselineManager {
+        authority: Pubkey,
+        total_providers: u64,
+        total_subscriptions: u64,
+        bump: u8,
+    }
+
+    #[test]
+    fn migrated_baseline_manager_counts_plans_and_keeps_exits_open() {
+        let baseline = BaselineManager {
+            authority: Pubkey::new_unique(),
+            total_providers: 7,
+            total_subscriptions: 30,
+            bump: 255,
+        };
+        let mut data = SubscriptionManager::DISCRIMINATOR.to_vec();
+        baseline.serialize(&mut data).unwrap();
+        data.resize(8 + SubscriptionManager::INIT_SPACE, 0);
+
+        let mut manager = SubscriptionManager::try_deserialize(&mut &data[..]).unwrap();
+        manager.apply_migration_defaults();
+
+        assert_eq!(manager.total_plans, 7);
+        assert_eq!(manager.total_providers, 0);
+        assert_eq!(manager.total_subscriptions, 30);
+        assert!(manager.pending_authority.is_none());
+        manager.paused = true;
+        assert!(!manager.is_paused(PAUSE_EXITS));
+        assert!(manager.is_paused(PAUSE_SUBSCRIBE));
+    }
 }
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use crate::state::Subscription;
+
+/// Wallet sharing an owner's subscription on a group plan
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionMember {
+    pub subscription: Pubkey,
+    pub member: Pubkey,
+    pub invited_at: i64,
+    /// Set once the invited wallet accepts; pending invites grant no access
+    pub accepted_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl SubscriptionMember {
+    /// An accepted link grants access for as long as the owner's subscription does
+    pub fn is_entitled(&self, subscription_key: Pubkey, subscription: &Subscription, now: i64) -> bool {
+        self.subscription == subscription_key
+            && self.accepted_at.is_some()
+            && subscription.is_entitled(now)
+    }
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::util::constants::*;
+use crate::util::pricing::price_units;
 
 #[account]
 #[derive(InitSpace)]  
@@ -20,4 +21,205 @@ pub struct SubscriptionPlan {
     pub is_active: bool,
     pub created_at: i64,
     pub bump: u8,
+    #[max_len(MAX_PREPAY_BUNDLES)]
+    pub prepay_bundles: Vec<PrepayBundle>,
+    pub metered: Option<MeteredComponent>,
+    pub pricing_schedule: Option<PricingSchedule>,
+    /// Seats held across all subscriptions, checked against `max_subscribers`
+    pub current_seats: u32,
+    /// Notice date after which the plan stops renewing, set by `sunset_plan`
+    pub sunset_at: Option<i64>,
+    /// Wallet authorized to manage the plan. Starts as `provider`, which stays fixed
+    /// because it seeds the plan and vault addresses.
+    pub owner: Pubkey,
+    pub pending_owner: Option<Pubkey>,
+    /// Wallets each subscription can share access with; zero for single-user plans
+    pub max_members: u16,
+    /// Non-transferable Token-2022 mint issued to subscribers, if enabled
+    pub membership_mint: Option<Pubkey>,
+    /// Share of each renewal paid to the keeper that processes it, if any
+    pub crank_reward: Option<CrankReward>,
+}
+
+/// Keeper reward taken out of the provider's share of a renewal
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum CrankReward {
+    Fixed { amount: u64 },
+    /// Basis points of the renewal amount, up to `cap`
+    Bps { bps: u16, cap: u64 },
+}
+
+/// A multi-period prepay offer, e.g. 12 periods for the price of 10
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PrepayBundle {
+    pub periods: u16,
+    pub price: u64,
+}
+
+/// Usage-based component billed on top of `price_per_period`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct MeteredComponent {
+    pub unit_price: u64,
+    pub included_units: u64,
+    /// Maximum usage charge per period, if any
+    pub overage_cap: Option<u64>,
+}
+
+impl MeteredComponent {
+    /// Whether usage has a price: a flat `unit_price`, or a schedule that replaces it
+    pub fn is_priced(&self, schedule: Option<&PricingSchedule>) -> bool {
+        self.unit_price > 0 || schedule.is_some()
+    }
+
+    /// Usage charge for `units` reported in a period. Units beyond `included_units` are
+    /// priced by `schedule` when present, otherwise at the flat `unit_price`.
+    /// Returns `None` on overflow.
+    pub fn overage_charge(&self, units: u64, schedule: Option<&PricingSchedule>) -> Option<u64> {
+        let billable_units = units.saturating_sub(self.included_units);
+        let charge = match schedule {
+            Some(schedule) => price_units(schedule.model, &schedule.tiers, billable_units)?,
+            None => billable_units.checked_mul(self.unit_price)?,
+        };
+        Some(match self.overage_cap {
+            Some(cap) => charge.min(cap),
+            None => charge,
+        })
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PricingModel {
+    /// Each tier prices only the units that fall inside it
+    Graduated,
+    /// All units are priced at the rate of the tier the total reaches
+    Volume,
+}
+
+/// Upper bound (inclusive) and unit price of a pricing tier. The last tier has no bound.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct PricingTier {
+    pub up_to: Option<u64>,
+    pub unit_price: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PricingSchedule {
+    pub model: PricingModel,
+    #[max_len(MAX_PRICING_TIERS)]
+    pub tiers: Vec<PricingTier>,
+}
+
+impl SubscriptionPlan {
+    /// Fill fields the older layout lacked: the provider owns the plan and every
+    /// existing subscription holds one seat
+    pub fn apply_migration_defaults(&mut self) {
+        if self.owner == Pubkey::default() {
+            self.owner = self.provider;
+        }
+        if self.current_seats == 0 {
+            self.current_seats = self.current_subscribers;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(included_units: u64, overage_cap: Option<u64>) -> MeteredComponent {
+        MeteredComponent { unit_price: 3, included_units, overage_cap }
+    }
+
+    fn schedule() -> PricingSchedule {
+        PricingSchedule {
+            model: PricingModel::Graduated,
+            tiers: vec![
+                PricingTier { up_to: Some(10), unit_price: 10 },
+                PricingTier { up_to: None, unit_price: 1 },
+            ],
+        }
+    }
+
+    #[test]
+    fn included_units_are_not_charged() {
+        let metered = component(100, None);
+        assert_eq!(metered.overage_charge(0, None), Some(0));
+        assert_eq!(metered.overage_charge(100, None), Some(0));
+        assert_eq!(metered.overage_charge(101, None), Some(3));
+        assert_eq!(metered.overage_charge(150, None), Some(150));
+        // Tiers start counting after the included units
+        assert_eq!(metered.overage_charge(105, Some(&schedule())), Some(50));
+        assert_eq!(metered.overage_charge(120, Some(&schedule())), Some(100 + 10));
+    }
+
+    #[test]
+    fn overage_cap_limits_the_usage_charge() {
+        let metered = component(10, Some(60));
+        assert_eq!(metered.overage_charge(20, None), Some(30));
+        assert_eq!(metered.overage_charge(30, None), Some(60));
+        assert_eq!(metered.overage_charge(1_000, None), Some(60));
+        assert_eq!(metered.overage_charge(u64::MAX, None), None);
+        assert_eq!(metered.overage_charge(1_000, Some(&schedule())), Some(60));
+    }
+
+    #[test]
+    fn zero_unit_price_needs_a_schedule() {
+        let metered = MeteredComponent { unit_price: 0, included_units: 0, overage_cap: None };
+        assert!(!metered.is_priced(None));
+        assert!(metered.is_priced(Some(&schedule())));
+        assert!(component(0, None).is_priced(None));
+    }
+
+    /// Plan layout before any fields were added
+    #[derive(AnchorSerialize)]
+    struct BaselinePlan {
+        provider: Pubkey,
+        plan_id: String,
+        name: String,
+        description: String,
+        price_per_period: u64,
+        period_duration_seconds: i64,
+        payment_token: Pubkey,
+        max_subscribers: Option<u32>,
+        current_subscribers: u32,
+        total_revenue: u64,
+        is_active: bool,
+        created_at: i64,
+        bump: u8,
+    }
+
+    #[test]
+    fn migrated_baseline_plan_is_owned_by_its_provider() {
+        let provider = Pubkey::new_unique();
+        let baseline = BaselinePlan {
+            provider,
+            plan_id: "pro".to_string(),
+            name: "Pro".to_string(),
+            description: "Everything".to_string(),
+            price_per_period: 100,
+            period_duration_seconds: 1_000,
+            payment_token: Pubkey::new_unique(),
+            max_subscribers: Some(10),
+            current_subscribers: 4,
+            total_revenue: 1_200,
+            is_active: true,
+            created_at: 500,
+            bump: 253,
+        };
+        let mut data = SubscriptionPlan::DISCRIMINATOR.to_vec();
+        baseline.serialize(&mut data).unwrap();
+        data.resize(8 + SubscriptionPlan::INIT_SPACE, 0);
+
+        let mut plan = SubscriptionPlan::try_deserialize(&mut &data[..]).unwrap();
+        plan.apply_migration_defaults();
+
+        assert_eq!(plan.owner, provider);
+        assert_eq!(plan.current_seats, 4);
+        assert_eq!(plan.description, "Everything");
+        assert_eq!(plan.total_revenue, 1_200);
+        assert!(plan.prepay_bundles.is_empty());
+        assert!(plan.sunset_at.is_none());
+        assert!(plan.membership_mint.is_none());
+        assert!(plan.crank_reward.is_none());
+    }
 }
@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+/// A key the manager authority has delegated provider verification to
+#[account]
+#[derive(InitSpace)]
+pub struct Verifier {
+    pub verifier: Pubkey,
+    pub added_by: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, TokenAccount, Transfer};
+use crate::error::SubscriptionError;
+use crate::state::{Provider, Subscription, SubscriptionPlan};
+use crate::util::constants::*;
+
+/// End a subscription on the provider's side: refund its unused value from the plan vault
+/// to whoever paid for it, deactivate it and release its subscriber and seat counts.
+/// The subscriber's share goes to `subscriber_refund_to` and the sponsor's share to
+/// `sponsor_refund_to`, which the caller has checked with
+/// `Subscription::is_sponsor_refund_account`; each is only required when its share is non-zero.
+/// Returns the amounts refunded to the subscriber and to the sponsor.
+pub fn cancel_with_refund<'info>(
+    subscription: &mut Account<'info, Subscription>,
+    plan: &mut Account<'info, SubscriptionPlan>,
+    provider_account: &mut Account<'info, Provider>,
+    provider_vault: &Account<'info, TokenAccount>,
+    subscriber_refund_to: Option<AccountInfo<'info>>,
+    sponsor_refund_to: Option<AccountInfo<'info>>,
+    token_program: AccountInfo<'info>,
+) -> Result<(u64, u64)> {
+    require!(subscription.is_active, SubscriptionError::SubscriptionInactive);
+
+    let now = Clock::get()?.unix_timestamp;
+    let (subscriber_refund, sponsor_refund) = subscription
+        .unused_value_by_payer(now)
+        .ok_or(SubscriptionError::ArithmeticOverflow)?;
+    let total_refund = subscriber_refund
+        .checked_add(sponsor_refund)
+        .ok_or(SubscriptionError::ArithmeticOverflow)?;
+    require!(
+        provider_vault.amount >= total_refund,
+        SubscriptionError::InsufficientFunds
+    );
+
+    // The vault is owned by the plan PDA, which signs the refunds
+    let plan_seeds = &[
+        SUBSCRIPTION_PLAN_SEED,
+        plan.provider.as_ref(),
+        plan.plan_id.as_bytes(),
+        &[plan.bump],
+    ];
+
+    for (amount, refund_to) in [(subscriber_refund, subscriber_refund_to), (sponsor_refund, sponsor_refund_to)] {
+        if amount == 0 {
+            continue;
+        }
+        let refund_to = refund_to.ok_or(SubscriptionError::InvalidRefundAccount)?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: provider_vault.to_account_info(),
+                    to: refund_to,
+                    authority: plan.to_account_info(),
+                },
+                &[plan_seeds],
+            ),
+            amount,
+        )?;
+    }
+
+    subscription.is_active = false;
+    subscription.cancelled_at = Some(now);
+    subscription.credit_balance = 0;
+
+    plan.current_subscribers = plan.current_subscribers.saturating_sub(1);
+    plan.current_seats = plan.current_seats.saturating_sub(subscription.quantity);
+    provider_account.active_subscribers = provider_account.active_subscribers.saturating_sub(1);
+
+    Ok((subscriber_refund, sponsor_refund))
+}
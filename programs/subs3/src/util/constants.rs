@@ -2,6 +2,22 @@
 pub const MAX_PLAN_ID_LENGTH: usize = 32;
 pub const MAX_NAME_LENGTH: usize = 64;
 pub const MAX_DESCRIPTION_LENGTH: usize = 256;
+pub const MAX_METADATA_URI_LENGTH: usize = 200;
+
+// Maximum number of prepay bundles a plan can offer
+pub const MAX_PREPAY_BUNDLES: usize = 4;
+
+// Maximum number of tiers in a metered pricing schedule
+pub const MAX_PRICING_TIERS: usize = 8;
+
+// Maximum number of add-ons attached to a single subscription
+pub const MAX_ADD_ONS_PER_SUBSCRIPTION: usize = 4;
+
+// Number of recent payments kept in a subscription's payment history
+pub const PAYMENT_HISTORY_LENGTH: usize = 8;
+
+// Maximum number of delegated roles on a provider account
+pub const MAX_PROVIDER_ROLES: usize = 8;
 
 // Minimum period duration (1 hour in seconds)
 pub const MIN_PERIOD_DURATION: i64 = 3600;
@@ -9,17 +25,79 @@ pub const MIN_PERIOD_DURATION: i64 = 3600;
 // Maximum period duration (1 year in seconds)
 pub const MAX_PERIOD_DURATION: i64 = 31_536_000;
 
+// Minimum notice before a plan sunset (7 days in seconds)
+pub const MIN_SUNSET_NOTICE: i64 = 604_800;
+
 // Subscription manager seed
 pub const SUBSCRIPTION_MANAGER_SEED: &[u8] = b"subscription_manager";
 
+// Provider registry seed
+pub const PROVIDER_SEED: &[u8] = b"provider";
+
+// Delegated verifier seed
+pub const VERIFIER_SEED: &[u8] = b"verifier";
+
+// Highest provider verification level
+pub const MAX_VERIFICATION_LEVEL: u8 = 3;
+
+// Provider blocklist seed
+pub const BLOCKED_SUBSCRIBER_SEED: &[u8] = b"blocked_subscriber";
+
 // Subscription plan seed
 pub const SUBSCRIPTION_PLAN_SEED: &[u8] = b"subscription_plan";
 
 // Subscription seed
 pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
 
+// Per-subscription payment delegate seed, approved on token accounts for batched renewals
+pub const PAYMENT_DELEGATE_SEED: &[u8] = b"payment_delegate";
+
+// Group plan member seed
+pub const SUBSCRIPTION_MEMBER_SEED: &[u8] = b"subscription_member";
+
+// Plan membership token mint seed
+pub const MEMBERSHIP_MINT_SEED: &[u8] = b"membership_mint";
+
+// Payment receipt seed
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+// Allowed payment mint seed
+pub const ALLOWED_MINT_SEED: &[u8] = b"allowed_mint";
+
+// Add-on seed
+pub const ADD_ON_SEED: &[u8] = b"add_on";
+
+// Organization seeds
+pub const ORGANIZATION_SEED: &[u8] = b"organization";
+pub const ORGANIZATION_VAULT_SEED: &[u8] = b"organization_vault";
+pub const ORGANIZATION_MEMBER_SEED: &[u8] = b"organization_member";
+
 // Provider vault seed
 pub const PROVIDER_VAULT_SEED: &[u8] = b"provider_vault";
 
+// Pause categories on SubscriptionManager.pause_flags
+pub const PAUSE_SUBSCRIBE: u8 = 1 << 0;
+pub const PAUSE_PAYMENTS: u8 = 1 << 1;
+pub const PAUSE_PLAN_CREATION: u8 = 1 << 2;
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 3;
+pub const PAUSE_CANCELLATIONS: u8 = 1 << 4;
+pub const PAUSE_ALL: u8 = PAUSE_SUBSCRIBE | PAUSE_PAYMENTS | PAUSE_PLAN_CREATION | PAUSE_WITHDRAWALS | PAUSE_CANCELLATIONS;
+
+// Categories that let users leave the program, optionally allowed during a global pause
+pub const PAUSE_EXITS: u8 = PAUSE_WITHDRAWALS | PAUSE_CANCELLATIONS;
+
+// Rolling window for the subscriber's cumulative spending limit, tracked in daily buckets
+pub const SPENDING_WINDOW_DAYS: usize = 30;
+
+// Daily buckets kept for the spending window; the extra day covers the partial first day,
+// so a charge always counts for at least the full window
+pub const SPENDING_BUCKETS: usize = SPENDING_WINDOW_DAYS + 1;
+
+// Length of one spending bucket (1 day in seconds)
+pub const SPENDING_BUCKET_SECONDS: i64 = 86_400;
+
+// Basis point denominator for percentage-based amounts
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
 // Payment grace period (5 minutes in seconds) - allows for small timing discrepancies
 pub const PAYMENT_GRACE_PERIOD: i64 = 300;
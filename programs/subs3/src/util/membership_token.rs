@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_spl::token_2022::{self, Burn, InitializeMint2, MintTo, Token2022};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+use anchor_spl::token_2022_extensions::{
+    non_transferable_mint_initialize, permanent_delegate_initialize,
+    NonTransferableMintInitialize, PermanentDelegateInitialize,
+};
+use anchor_spl::token_interface;
+use crate::error::SubscriptionError;
+use crate::event::{MembershipTokenBurned, MembershipTokenMinted};
+use crate::state::SubscriptionPlan;
+use crate::util::constants::*;
+
+/// Create a plan's membership mint at its PDA: zero decimals, non-transferable, with the
+/// plan PDA as both mint authority and permanent delegate
+pub fn create_membership_mint<'info>(
+    plan: &Account<'info, SubscriptionPlan>,
+    mint: AccountInfo<'info>,
+    mint_bump: u8,
+    payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<()> {
+    let plan_key = plan.key();
+    let mint_seeds = &[MEMBERSHIP_MINT_SEED, plan_key.as_ref(), &[mint_bump]];
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::PermanentDelegate,
+    ])?;
+
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program,
+            CreateAccount {
+                from: payer,
+                to: mint.clone(),
+            },
+            &[mint_seeds],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        token_program.key,
+    )?;
+
+    // Extensions have to be initialized before the mint itself
+    non_transferable_mint_initialize(CpiContext::new(
+        token_program.clone(),
+        NonTransferableMintInitialize {
+            token_program_id: token_program.clone(),
+            mint: mint.clone(),
+        },
+    ))?;
+    permanent_delegate_initialize(
+        CpiContext::new(
+            token_program.clone(),
+            PermanentDelegateInitialize {
+                token_program_id: token_program.clone(),
+                mint: mint.clone(),
+            },
+        ),
+        &plan_key,
+    )?;
+
+    token_2022::initialize_mint2(
+        CpiContext::new(token_program, InitializeMint2 { mint }),
+        0,
+        &plan_key,
+        None,
+    )
+}
+
+/// Mint one membership token to `to`. The plan PDA is the mint authority.
+pub fn issue_membership_token<'info>(
+    plan: &Account<'info, SubscriptionPlan>,
+    mint: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    let plan_seeds = &[
+        SUBSCRIPTION_PLAN_SEED,
+        plan.provider.as_ref(),
+        plan.plan_id.as_bytes(),
+        &[plan.bump],
+    ];
+
+    token_2022::mint_to(
+        CpiContext::new_with_signer(
+            token_program,
+            MintTo {
+                mint,
+                to,
+                authority: plan.to_account_info(),
+            },
+            &[plan_seeds],
+        ),
+        1,
+    )
+}
+
+/// Burn `amount` membership tokens from `from`. The plan PDA is the mint's permanent
+/// delegate, so no signature from the holder is needed.
+pub fn revoke_membership_token<'info>(
+    plan: &Account<'info, SubscriptionPlan>,
+    mint: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let plan_seeds = &[
+        SUBSCRIPTION_PLAN_SEED,
+        plan.provider.as_ref(),
+        plan.plan_id.as_bytes(),
+        &[plan.bump],
+    ];
+
+    token_2022::burn(
+        CpiContext::new_with_signer(
+            token_program,
+            Burn {
+                mint,
+                from,
+                authority: plan.to_account_info(),
+            },
+            &[plan_seeds],
+        ),
+        amount,
+    )
+}
+
+/// Mint a membership token to `to` if the plan issues them and it holds none yet.
+/// The accounts are required whenever the plan issues membership tokens.
+pub fn issue_membership_token_if_enabled<'info>(
+    plan: &Account<'info, SubscriptionPlan>,
+    mint: Option<&InterfaceAccount<'info, token_interface::Mint>>,
+    to: Option<&InterfaceAccount<'info, token_interface::TokenAccount>>,
+    token_program: Option<&Program<'info, Token2022>>,
+) -> Result<()> {
+    if plan.membership_mint.is_none() {
+        return Ok(());
+    }
+    let (Some(mint), Some(to), Some(token_program)) = (mint, to, token_program) else {
+        return err!(SubscriptionError::MembershipAccountsMissing);
+    };
+    if to.amount > 0 {
+        return Ok(());
+    }
+
+    issue_membership_token(
+        plan,
+        mint.to_account_info(),
+        to.to_account_info(),
+        token_program.to_account_info(),
+    )?;
+
+    emit!(MembershipTokenMinted {
+        subscription_plan: plan.key(),
+        holder: to.owner,
+        mint: mint.key(),
+    });
+
+    Ok(())
+}
+
+/// Burn every membership token `from` holds if the plan issues them.
+/// The accounts are required whenever the plan issues membership tokens.
+pub fn revoke_membership_token_if_enabled<'info>(
+    plan: &Account<'info, SubscriptionPlan>,
+    mint: Option<&InterfaceAccount<'info, token_interface::Mint>>,
+    from: Option<&InterfaceAccount<'info, token_interface::TokenAccount>>,
+    token_program: Option<&Program<'info, Token2022>>,
+) -> Result<()> {
+    if plan.membership_mint.is_none() {
+        return Ok(());
+    }
+    let (Some(mint), Some(from), Some(token_program)) = (mint, from, token_program) else {
+        return err!(SubscriptionError::MembershipAccountsMissing);
+    };
+    if from.amount == 0 {
+        return Ok(());
+    }
+
+    revoke_membership_token(
+        plan,
+        mint.to_account_info(),
+        from.to_account_info(),
+        token_program.to_account_info(),
+        from.amount,
+    )?;
+
+    emit!(MembershipTokenBurned {
+        subscription_plan: plan.key(),
+        holder: from.owner,
+        mint: mint.key(),
+        amount: from.amount,
+    });
+
+    Ok(())
+}
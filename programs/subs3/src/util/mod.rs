@@ -1,3 +1,11 @@
 pub mod constants;
+pub mod pricing;
+pub mod membership_token;
+pub mod cancellation;
+pub mod migration;
 
 pub use constants::*;
+pub use pricing::*;
+pub use membership_token::*;
+pub use cancellation::*;
+pub use migration::*;
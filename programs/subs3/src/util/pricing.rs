@@ -0,0 +1,205 @@
+use crate::state::{CrankReward, PricingModel, PricingTier};
+use crate::util::constants::*;
+
+/// Check that tiers are non-empty, strictly increasing, and end with an unbounded tier
+pub fn is_valid_schedule(tiers: &[PricingTier]) -> bool {
+    if tiers.len() > MAX_PRICING_TIERS {
+        return false;
+    }
+
+    let Some((last, bounded)) = tiers.split_last() else {
+        return false;
+    };
+    if last.up_to.is_some() {
+        return false;
+    }
+
+    let mut previous_bound = 0u64;
+    for tier in bounded {
+        match tier.up_to {
+            Some(up_to) if up_to > previous_bound => previous_bound = up_to,
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Price `units` against a tier schedule. Returns `None` on overflow or if no tier covers
+/// the units, which cannot happen for a schedule accepted by `is_valid_schedule`.
+pub fn price_units(model: PricingModel, tiers: &[PricingTier], units: u64) -> Option<u64> {
+    if units == 0 {
+        return Some(0);
+    }
+
+    match model {
+        PricingModel::Graduated => {
+            let mut total = 0u64;
+            let mut lower_bound = 0u64;
+            for tier in tiers {
+                let upper_bound = tier.up_to.map_or(units, |up_to| up_to.min(units));
+                let tier_units = upper_bound.saturating_sub(lower_bound);
+                total = total.checked_add(tier_units.checked_mul(tier.unit_price)?)?;
+                if upper_bound >= units {
+                    return Some(total);
+                }
+                lower_bound = upper_bound;
+            }
+            None
+        }
+        PricingModel::Volume => {
+            let tier = tiers.iter().find(|tier| match tier.up_to {
+                Some(up_to) => units <= up_to,
+                None => true,
+            })?;
+            units.checked_mul(tier.unit_price)
+        }
+    }
+}
+
+/// Portion of `amount` covering `remaining_seconds` of a `period_seconds` long period.
+/// Rounds down; returns `None` on overflow or a non-positive period.
+pub fn prorate(amount: u64, remaining_seconds: i64, period_seconds: i64) -> Option<u64> {
+    if period_seconds <= 0 {
+        return None;
+    }
+    if remaining_seconds <= 0 {
+        return Some(0);
+    }
+    let prorated = (amount as u128)
+        .checked_mul(remaining_seconds as u128)?
+        / period_seconds as u128;
+    u64::try_from(prorated).ok()
+}
+
+/// Part of `amount`, paid for the time from `from` to `until`, that is still unused at `now`.
+/// Rounds down; returns `None` on overflow.
+pub fn unused_share(amount: u64, from: i64, until: i64, now: i64) -> Option<u64> {
+    let start = now.max(from);
+    if until <= start {
+        return Some(0);
+    }
+    prorate(amount, until.checked_sub(start)?, until.checked_sub(from)?)
+}
+
+/// Keeper reward for a renewal of `amount`, capped at the protocol's `max` and never more
+/// than the renewal itself. The protocol minimum only applies when a reward is configured,
+/// so a bps reward on a small renewal is not lifted above what the provider chose
+pub fn crank_reward_amount(reward: &CrankReward, amount: u64, max: u64) -> u64 {
+    let reward = match *reward {
+        CrankReward::Fixed { amount } => amount,
+        CrankReward::Bps { bps, cap } => {
+            let share = amount as u128 * bps as u128 / BPS_DENOMINATOR as u128;
+            u64::try_from(share).unwrap_or(u64::MAX).min(cap)
+        }
+    };
+    reward.min(max).min(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiers() -> Vec<PricingTier> {
+        vec![
+            PricingTier { up_to: Some(1_000), unit_price: 10 },
+            PricingTier { up_to: Some(11_000), unit_price: 5 },
+            PricingTier { up_to: None, unit_price: 2 },
+        ]
+    }
+
+    #[test]
+    fn graduated_prices_each_tier_separately() {
+        let tiers = tiers();
+        assert_eq!(price_units(PricingModel::Graduated, &tiers, 0), Some(0));
+        assert_eq!(price_units(PricingModel::Graduated, &tiers, 500), Some(5_000));
+        assert_eq!(price_units(PricingModel::Graduated, &tiers, 1_000), Some(10_000));
+        assert_eq!(price_units(PricingModel::Graduated, &tiers, 1_001), Some(10_005));
+        assert_eq!(
+            price_units(PricingModel::Graduated, &tiers, 12_000),
+            Some(10_000 + 50_000 + 2_000)
+        );
+    }
+
+    #[test]
+    fn volume_prices_all_units_at_reached_tier() {
+        let tiers = tiers();
+        assert_eq!(price_units(PricingModel::Volume, &tiers, 0), Some(0));
+        assert_eq!(price_units(PricingModel::Volume, &tiers, 1_000), Some(10_000));
+        assert_eq!(price_units(PricingModel::Volume, &tiers, 1_001), Some(5_005));
+        assert_eq!(price_units(PricingModel::Volume, &tiers, 12_000), Some(24_000));
+    }
+
+    #[test]
+    fn pricing_overflow_returns_none() {
+        let tiers = vec![PricingTier { up_to: None, unit_price: u64::MAX }];
+        assert_eq!(price_units(PricingModel::Graduated, &tiers, 2), None);
+        assert_eq!(price_units(PricingModel::Volume, &tiers, 2), None);
+    }
+
+    #[test]
+    fn bounded_last_tier_does_not_price() {
+        let tiers = vec![PricingTier { up_to: Some(10), unit_price: 1 }];
+        assert_eq!(price_units(PricingModel::Graduated, &tiers, 11), None);
+        assert_eq!(price_units(PricingModel::Volume, &tiers, 11), None);
+    }
+
+    #[test]
+    fn prorate_scales_by_remaining_time() {
+        assert_eq!(prorate(100, 50, 100), Some(50));
+        assert_eq!(prorate(100, 33, 100), Some(33));
+        assert_eq!(prorate(100, 0, 100), Some(0));
+        assert_eq!(prorate(100, -10, 100), Some(0));
+        assert_eq!(prorate(100, 250, 100), Some(250));
+        assert_eq!(prorate(u64::MAX, 2, 1), None);
+        assert_eq!(prorate(100, 10, 0), None);
+    }
+
+    #[test]
+    fn unused_share_counts_only_time_left() {
+        assert_eq!(unused_share(100, 0, 100, -50), Some(100));
+        assert_eq!(unused_share(100, 0, 100, 0), Some(100));
+        assert_eq!(unused_share(100, 0, 100, 25), Some(75));
+        assert_eq!(unused_share(100, 0, 100, 100), Some(0));
+        assert_eq!(unused_share(100, 0, 100, 150), Some(0));
+        assert_eq!(unused_share(100, 50, 50, 0), Some(0));
+        assert_eq!(unused_share(100, 80, 50, 0), Some(0));
+    }
+
+    #[test]
+    fn crank_reward_is_bounded() {
+        let fixed = CrankReward::Fixed { amount: 50 };
+        assert_eq!(crank_reward_amount(&fixed, 1_000, 100), 50);
+        assert_eq!(crank_reward_amount(&fixed, 1_000, 20), 20);
+        assert_eq!(crank_reward_amount(&fixed, 30, 100), 30);
+
+        let bps = CrankReward::Bps { bps: 100, cap: 25 };
+        assert_eq!(crank_reward_amount(&bps, 1_000, 100), 10);
+        assert_eq!(crank_reward_amount(&bps, 10_000, 100), 25);
+        assert_eq!(crank_reward_amount(&bps, u64::MAX, u64::MAX), 25);
+        assert_eq!(crank_reward_amount(&bps, 1_000, 0), 0);
+        // Small renewals pay the provider's share, not the protocol minimum
+        assert_eq!(crank_reward_amount(&bps, 100, 100), 1);
+    }
+
+    #[test]
+    fn schedule_validation() {
+        assert!(is_valid_schedule(&tiers()));
+        assert!(is_valid_schedule(&[PricingTier { up_to: None, unit_price: 0 }]));
+        assert!(!is_valid_schedule(&[]));
+        assert!(!is_valid_schedule(&[PricingTier { up_to: Some(10), unit_price: 1 }]));
+        assert!(!is_valid_schedule(&[
+            PricingTier { up_to: Some(10), unit_price: 1 },
+            PricingTier { up_to: Some(10), unit_price: 1 },
+            PricingTier { up_to: None, unit_price: 1 },
+        ]));
+        assert!(!is_valid_schedule(&[
+            PricingTier { up_to: Some(0), unit_price: 1 },
+            PricingTier { up_to: None, unit_price: 1 },
+        ]));
+        assert!(!is_valid_schedule(&[
+            PricingTier { up_to: None, unit_price: 1 },
+            PricingTier { up_to: None, unit_price: 1 },
+        ]));
+    }
+}